@@ -0,0 +1,156 @@
+//! Exporting the upcoming reviews as an iCalendar file, to be imported in a calendar app.
+
+use std::collections::BTreeMap;
+
+use chrono::{Days, NaiveDate, NaiveDateTime};
+
+use crate::{Graph, IOBackend, Id, LessonStatus};
+
+/// The longest a line of an iCalendar file can be, in bytes, before it has to be folded.
+const MAX_LINE_LENGTH: usize = 75;
+
+/// How the reviews are turned into calendar events.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CalendarEvents {
+    /// One event per day with due lessons, listing them.
+    PerDay,
+    /// One event per lesson, on the day it is due.
+    PerLesson,
+}
+
+impl<T: IOBackend> Graph<T> {
+    /// Return the practiced lessons grouped by the day they are due, sorted by name in each day.
+    /// Lessons that are already overdue are due `today`.
+    pub fn due_lessons_per_day(&self, today: NaiveDate) -> BTreeMap<NaiveDate, Vec<Id>> {
+        let mut days: BTreeMap<NaiveDate, Vec<Id>> = BTreeMap::new();
+        for (&id, node) in &self.nodes {
+            if let LessonStatus::Practiced { good_until, .. } = node.lesson.status {
+                days.entry(std::cmp::max(good_until, today))
+                    .or_default()
+                    .push(id);
+            }
+        }
+        for ids in days.values_mut() {
+            ids.sort_by(|a, b| {
+                let name_a = &self.nodes[a].lesson.name;
+                let name_b = &self.nodes[b].lesson.name;
+                name_a.cmp(name_b).then(a.cmp(b))
+            });
+        }
+        days
+    }
+
+    /// Write the upcoming reviews as an iCalendar file, with all-day events. `stamp` is the
+    /// current UTC time, which every event needs. Events keep the same UID from one export to the
+    /// next, so that importing a new export updates the previous one.
+    pub fn to_icalendar(
+        &self,
+        today: NaiveDate,
+        stamp: NaiveDateTime,
+        events: CalendarEvents,
+    ) -> String {
+        let stamp = stamp.format("%Y%m%dT%H%M%SZ").to_string();
+        let mut lines = vec![
+            String::from("BEGIN:VCALENDAR"),
+            String::from("VERSION:2.0"),
+            String::from("PRODID:-//buisson//buisson//EN"),
+            String::from("CALSCALE:GREGORIAN"),
+        ];
+
+        for (day, ids) in self.due_lessons_per_day(today) {
+            let names = ids
+                .iter()
+                .map(|id| self.nodes[id].lesson.name.as_str())
+                .collect::<Vec<_>>();
+            match events {
+                CalendarEvents::PerDay => {
+                    let summary = format!(
+                        "{} lesson{} to review",
+                        names.len(),
+                        if names.len() == 1 { "" } else { "s" }
+                    );
+                    lines.extend(event(
+                        &format!("{}@buisson", day.format("%Y%m%d")),
+                        &stamp,
+                        day,
+                        &summary,
+                        Some(&names.join("\n")),
+                    ));
+                }
+                CalendarEvents::PerLesson => {
+                    for (id, name) in ids.iter().zip(names) {
+                        // the uid doesn't depend on the day, so that importing a newer export
+                        // moves the event of a rescheduled lesson
+                        lines.extend(event(
+                            &format!("{}@buisson", self.nodes[id].lesson.uid),
+                            &stamp,
+                            day,
+                            &format!("Review {}", name),
+                            None,
+                        ));
+                    }
+                }
+            }
+        }
+
+        lines.push(String::from("END:VCALENDAR"));
+        lines.iter().map(|line| fold(line)).collect()
+    }
+}
+
+/// The lines of an all-day event on `day`.
+fn event(
+    uid: &str,
+    stamp: &str,
+    day: NaiveDate,
+    summary: &str,
+    description: Option<&str>,
+) -> Vec<String> {
+    let mut lines = vec![
+        String::from("BEGIN:VEVENT"),
+        format!("UID:{}", uid),
+        format!("DTSTAMP:{}", stamp),
+        format!("DTSTART;VALUE=DATE:{}", day.format("%Y%m%d")),
+        format!("DTEND;VALUE=DATE:{}", (day + Days::new(1)).format("%Y%m%d")),
+        format!("SUMMARY:{}", escape(summary)),
+    ];
+    if let Some(description) = description {
+        lines.push(format!("DESCRIPTION:{}", escape(description)));
+    }
+    lines.push(String::from("END:VEVENT"));
+    lines
+}
+
+/// Escape `text` to be the value of a text property.
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' | ';' | ',' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            '\n' => escaped.push_str("\\n"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Fold `line` into lines of at most `MAX_LINE_LENGTH` bytes, each continuation starting with a
+/// space, and end it with CRLF as the format requires. Characters are never split.
+fn fold(line: &str) -> String {
+    let mut folded = String::with_capacity(line.len() + 2);
+    let mut line_length = 0;
+    for c in line.chars() {
+        if line_length + c.len_utf8() > MAX_LINE_LENGTH {
+            folded.push_str("\r\n ");
+            // the leading space counts
+            line_length = 1;
+        }
+        folded.push(c);
+        line_length += c.len_utf8();
+    }
+    folded.push_str("\r\n");
+    folded
+}
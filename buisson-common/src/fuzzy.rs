@@ -0,0 +1,190 @@
+//! Subsequence fuzzy matching, used to search lessons by name. Matching ignores case and accents,
+//! so that "ecole" matches "École".
+
+use unicode_normalization::{char::is_combining_mark, UnicodeNormalization};
+
+/// Score given to every matched character.
+const MATCH_SCORE: i64 = 16;
+/// Bonus for a match at the start of a word.
+const WORD_START_BONUS: i64 = 8;
+/// Bonus for a match right after the previous one.
+const CONSECUTIVE_BONUS: i64 = 8;
+/// Maximum penalty for characters skipped before the first match.
+const MAX_LEADING_PENALTY: i64 = 3;
+
+/// The result of a successful match.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct FuzzyMatch {
+    /// The higher, the better the match.
+    pub score: i64,
+    /// The indices of the matched characters in the text, counted in `char`s and not in bytes.
+    pub matched_chars: Vec<usize>,
+}
+
+/// The characters `c` is compared as: lowercase, and without diacritics.
+fn fold(c: char) -> impl Iterator<Item = char> {
+    std::iter::once(c)
+        .nfd()
+        .filter(|&c| !is_combining_mark(c))
+        .flat_map(char::to_lowercase)
+}
+
+/// Return `text` as it is compared by the matcher: lowercase, and without diacritics.
+pub fn normalize(text: &str) -> String {
+    text.chars().flat_map(fold).collect()
+}
+
+/// Return whether all the characters of `pattern` appear in `text`, in order, and if so how well
+/// they do. When several alignments are possible, the best scoring one is returned.
+pub fn fuzzy_match(pattern: &str, text: &str) -> Option<FuzzyMatch> {
+    let pattern = pattern.chars().flat_map(fold).collect::<Vec<_>>();
+    if pattern.is_empty() {
+        return Some(FuzzyMatch::default());
+    }
+    // every folded character remembers the index of the character it comes from
+    let text = text
+        .chars()
+        .enumerate()
+        .flat_map(|(index, c)| fold(c).map(move |folded| (index, folded)))
+        .collect::<Vec<_>>();
+
+    let is_word_start = |j: usize| j == 0 || !text[j - 1].1.is_alphanumeric();
+
+    // `scores[i][j]` is the best score for matching `pattern[..=i]` with `pattern[i]` matched to
+    // `text[j]`, and `previous[i][j]` is where `pattern[i - 1]` was matched in that case.
+    let mut scores = vec![vec![None; text.len()]; pattern.len()];
+    let mut previous = vec![vec![0; text.len()]; pattern.len()];
+
+    for (i, &pattern_char) in pattern.iter().enumerate() {
+        // best value of `scores[i - 1][k] + k` for `k < j - 1`, used to compute gap penalties
+        let mut best_before: Option<(i64, usize)> = None;
+        for (j, &(_, text_char)) in text.iter().enumerate() {
+            if i > 0 && j >= 2 {
+                if let Some(score) = scores[i - 1][j - 2] {
+                    let candidate = score + (j - 2) as i64;
+                    if best_before.is_none_or(|(best, _)| candidate > best) {
+                        best_before = Some((candidate, j - 2));
+                    }
+                }
+            }
+
+            if text_char != pattern_char {
+                continue;
+            }
+
+            let bonus = MATCH_SCORE
+                + if is_word_start(j) {
+                    WORD_START_BONUS
+                } else {
+                    0
+                };
+
+            if i == 0 {
+                scores[i][j] = Some(bonus - std::cmp::min(j as i64, MAX_LEADING_PENALTY));
+                continue;
+            }
+
+            let consecutive = (j >= 1)
+                .then(|| scores[i - 1][j - 1])
+                .flatten()
+                .map(|score| (score + CONSECUTIVE_BONUS, j - 1));
+            let with_gap = best_before.map(|(best, k)| (best - (j - 1) as i64, k));
+
+            let best = match (consecutive, with_gap) {
+                (Some(a), Some(b)) => Some(if a.0 >= b.0 { a } else { b }),
+                (a, b) => a.or(b),
+            };
+
+            if let Some((score, k)) = best {
+                scores[i][j] = Some(score + bonus);
+                previous[i][j] = k;
+            }
+        }
+    }
+
+    let last = pattern.len() - 1;
+    let (mut j, score) = scores[last]
+        .iter()
+        .enumerate()
+        .filter_map(|(j, score)| score.map(|score| (j, score)))
+        .max_by_key(|&(j, score)| (score, std::cmp::Reverse(j)))?;
+
+    let mut matched_chars = vec![text[j].0];
+    for i in (1..=last).rev() {
+        j = previous[i][j];
+        matched_chars.push(text[j].0);
+    }
+    matched_chars.reverse();
+    matched_chars.dedup();
+
+    Some(FuzzyMatch {
+        score,
+        matched_chars,
+    })
+}
+
+/// Match `pattern` against every item, and return the ones that match, best matches first. Ties
+/// are broken by preferring shorter texts, then alphabetically.
+pub fn rank<'a, T>(
+    pattern: &str,
+    items: impl Iterator<Item = (T, &'a str)>,
+) -> Vec<(T, FuzzyMatch)> {
+    let mut matches = items
+        .filter_map(|(item, text)| fuzzy_match(pattern, text).map(|m| (item, text, m)))
+        .collect::<Vec<_>>();
+
+    matches.sort_by(|(_, text_a, match_a), (_, text_b, match_b)| {
+        match_b
+            .score
+            .cmp(&match_a.score)
+            .then(text_a.chars().count().cmp(&text_b.chars().count()))
+            .then(text_a.cmp(text_b))
+    });
+
+    matches
+        .into_iter()
+        .map(|(item, _, fuzzy_match)| (item, fuzzy_match))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_subsequence_matching() {
+        assert!(fuzzy_match("lnr", "Linear algebra").is_some());
+        assert!(fuzzy_match("algebra linear", "Linear algebra").is_none());
+        assert_eq!(
+            fuzzy_match("", "Linear algebra"),
+            Some(FuzzyMatch::default())
+        );
+    }
+
+    #[test]
+    fn test_case_and_accent_insensitivity() {
+        assert_eq!(
+            fuzzy_match("ecole", "École").unwrap().matched_chars,
+            vec![0, 1, 2, 3, 4]
+        );
+        assert!(fuzzy_match("ÉCO", "ecole").is_some());
+        // the indices count characters, not bytes
+        assert_eq!(
+            fuzzy_match("ge", "Théorème de Gödel")
+                .unwrap()
+                .matched_chars,
+            vec![12, 15]
+        );
+    }
+
+    #[test]
+    fn test_ranking() {
+        let items = ["Calculus", "Linear algebra", "Galois theory", "Algebra"];
+        let ranked = rank("alg", items.iter().map(|&name| (name, name)))
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect::<Vec<_>>();
+
+        assert_eq!(ranked, vec!["Algebra", "Linear algebra"]);
+    }
+}
@@ -0,0 +1,88 @@
+//! Lessons to reach before a deadline, like an exam.
+
+use chrono::{Days, NaiveDate};
+
+use crate::{path::days_to_study, BuissonError, Graph, IOBackend, Id, LessonStatus};
+
+/// How far a goal is from being reached.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GoalProgress {
+    pub id: Id,
+    pub deadline: NaiveDate,
+    /// how many lessons still need work before the goal is reached, the goal included.
+    pub remaining: usize,
+    /// how many days are left before the deadline.
+    pub days_left: i64,
+    /// how many lessons a day need to be studied to reach the goal in time.
+    pub needed_per_day: usize,
+    /// whether the goal can't be reached in time at the current pace.
+    pub at_risk: bool,
+}
+
+impl<T: IOBackend> Graph<T> {
+    /// Return the earliest deadline that lesson `id` needs to be ready for: its own, or the one of
+    /// a goal that depends on it. Deadlines that are already past are ignored.
+    pub fn deadline_for(&self, id: Id, today: NaiveDate) -> Option<NaiveDate> {
+        self.descendants(id)
+            .into_iter()
+            .chain([id])
+            .filter_map(|id| self.nodes[&id].lesson.deadline)
+            .filter(|deadline| *deadline >= today)
+            .min()
+    }
+
+    /// Return the progress of every goal whose deadline is not past, the earliest deadline first.
+    /// Goals are at risk when studying `lessons_per_day` lessons a day is not enough to reach
+    /// them in time.
+    pub fn goals(
+        &self,
+        today: NaiveDate,
+        lessons_per_day: u32,
+    ) -> Result<Vec<GoalProgress>, BuissonError<T>> {
+        let mut goals = self
+            .nodes
+            .iter()
+            .filter_map(|(&id, node)| {
+                let deadline = node.lesson.deadline.filter(|deadline| *deadline >= today)?;
+                let remaining = match self.learning_path(id) {
+                    Ok(path) => path.len(),
+                    Err(e) => return Some(Err(e)),
+                };
+                let days_left = (deadline - today).num_days();
+                Some(Ok(GoalProgress {
+                    id,
+                    deadline,
+                    remaining,
+                    days_left,
+                    needed_per_day: remaining.div_ceil(std::cmp::max(days_left, 1) as usize),
+                    at_risk: days_to_study(remaining, lessons_per_day) as i64 > days_left,
+                }))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        goals.sort_by_key(|goal| (goal.deadline, goal.id));
+        Ok(goals)
+    }
+}
+
+/// Shorten the interval of a lesson that was just studied with status `status`, so that it is
+/// reviewed the day before `deadline` instead of after it. Lessons studied the day before the
+/// deadline or later are left as is, since they are as fresh as they can be.
+pub fn cap_before_deadline(
+    status: LessonStatus,
+    deadline: NaiveDate,
+    today: NaiveDate,
+) -> LessonStatus {
+    let last_review = deadline - Days::new(1);
+    match status {
+        LessonStatus::Practiced {
+            level,
+            last_practiced,
+            good_until,
+        } if good_until > last_review && last_review > today => LessonStatus::Practiced {
+            level,
+            last_practiced,
+            good_until: last_review,
+        },
+        status => status,
+    }
+}
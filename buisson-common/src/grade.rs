@@ -0,0 +1,103 @@
+//! How well a study session went, and how it changes the level and the interval of a lesson.
+
+use chrono::Days;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::{days_from_level, LessonStatus};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Grade {
+    /// The lesson was forgotten, and should be studied again soon.
+    Again,
+    /// The lesson was remembered, but with difficulty.
+    Hard,
+    Good,
+    /// The lesson was remembered effortlessly.
+    Easy,
+}
+
+impl Grade {
+    pub const ALL: [Grade; 4] = [Grade::Again, Grade::Hard, Grade::Good, Grade::Easy];
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            Grade::Again => "Again",
+            Grade::Hard => "Hard",
+            Grade::Good => "Good",
+            Grade::Easy => "Easy",
+        }
+    }
+}
+
+impl LessonStatus {
+    /// The level a lesson with this status reaches after a study session graded `grade`. `Again`
+    /// drops two levels, `Hard` keeps the current one, `Good` goes up one level and `Easy` skips
+    /// one. A lesson that was never practiced starts at level 0, or 1 if it was easy. A known
+    /// lesson stays known, without a level, unless it was forgotten, in which case it starts over
+    /// at level 0.
+    pub fn level_after(&self, grade: Grade) -> Option<u32> {
+        let level = match (self, grade) {
+            (LessonStatus::Practiced { level, .. }, Grade::Again) => level.saturating_sub(2),
+            (LessonStatus::Practiced { level, .. }, Grade::Hard) => *level,
+            (LessonStatus::Practiced { level, .. }, Grade::Good) => level + 1,
+            (LessonStatus::Practiced { level, .. }, Grade::Easy) => level + 2,
+            (LessonStatus::GoodEnough, Grade::Again) => 0,
+            (LessonStatus::GoodEnough, _) => return None,
+            (LessonStatus::NotPracticed, Grade::Easy) => 1,
+            (LessonStatus::NotPracticed, _) => 0,
+        };
+        Some(level)
+    }
+
+    /// The number of days a lesson with this status is good for after a study session graded
+    /// `grade`, before the random variation applied by `new_status_if_graded`. `None` if the
+    /// lesson stays known, and is never due.
+    pub fn interval_after(&self, grade: Grade) -> Option<u64> {
+        let days = days_from_level(self.level_after(grade)?);
+        Some(match grade {
+            Grade::Again => 1,
+            Grade::Hard => std::cmp::max(days / 2, 1),
+            Grade::Good | Grade::Easy => days,
+        })
+    }
+
+    /// Return the status of a lesson with this status after it was studied today, and the session
+    /// was graded `grade`.
+    pub fn new_status_if_graded<R: Rng + ?Sized>(&self, grade: Grade, rng: &mut R) -> Self {
+        let (Some(level), Some(interval)) = (self.level_after(grade), self.interval_after(grade))
+        else {
+            return *self;
+        };
+        let today = chrono::offset::Local::now().date_naive();
+        let days_to_next = vary_interval(interval, rng);
+
+        Self::Practiced {
+            level,
+            last_practiced: today,
+            good_until: today + Days::new(days_to_next),
+        }
+    }
+}
+
+/// Whether a study session that changed the status of a lesson from `old` to `new` means the
+/// lesson had been forgotten: it had already been practiced, and either the session was graded
+/// `Again`, or the level of the lesson went down.
+pub fn is_lapse(old: &LessonStatus, new: &LessonStatus, grade: Option<Grade>) -> bool {
+    match (old, new) {
+        (LessonStatus::Practiced { .. }, _) if grade == Some(Grade::Again) => true,
+        (
+            LessonStatus::Practiced { level: old, .. },
+            LessonStatus::Practiced { level: new, .. },
+        ) => new < old,
+        (LessonStatus::Practiced { .. }, LessonStatus::NotPracticed) => true,
+        _ => false,
+    }
+}
+
+/// Pick a random number of days within 10% of `days`, so that lessons studied together don't
+/// stay due on the same days forever.
+pub(crate) fn vary_interval<R: Rng + ?Sized>(days: u64, rng: &mut R) -> u64 {
+    let diff = (0.1 * days as f64) as u64;
+    rng.gen_range(days - diff..=days + diff)
+}
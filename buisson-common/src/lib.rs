@@ -1,11 +1,72 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, fmt::Display, str::FromStr, time::Duration};
 
 use chrono::{Days, NaiveDate};
-use rand::{seq::IteratorRandom, Rng};
-use serde::{Deserialize, Serialize};
+use rand::{seq::IteratorRandom, thread_rng, Rng};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+pub mod calendar;
+pub mod fuzzy;
+pub mod goal;
+pub mod grade;
+pub mod path;
+pub mod query;
+pub mod recommend;
+pub mod redundant;
+pub mod restructure;
+pub mod sort;
+pub mod stats;
+pub mod sync;
+
+use fuzzy::FuzzyMatch;
+use grade::Grade;
+use query::{Query, QueryParseError};
 
 pub type Id = u64;
 
+/// An identifier that is unique across every copy of the lessons, unlike `Id`, which is only
+/// unique in a single `Graph`. It is made of 128 random bits, written as 32 hexadecimal digits.
+/// The nil `Uid`, made of zeros, is the one of lessons that were not given one yet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Default)]
+pub struct Uid(u128);
+
+impl Uid {
+    pub fn random<R: Rng + ?Sized>(rng: &mut R) -> Self {
+        // the odds of drawing the nil uid are negligible, but it would go unnoticed
+        Self(std::cmp::max(rng.gen(), 1))
+    }
+
+    pub fn is_nil(&self) -> bool {
+        self.0 == 0
+    }
+}
+
+impl Display for Uid {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:032x}", self.0)
+    }
+}
+
+impl FromStr for Uid {
+    type Err = std::num::ParseIntError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        u128::from_str_radix(s, 16).map(Self)
+    }
+}
+
+impl Serialize for Uid {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for Uid {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let text = String::deserialize(deserializer)?;
+        text.parse().map_err(serde::de::Error::custom)
+    }
+}
+
 fn days_from_level(level: u32) -> u64 {
     match level {
         0 => 1,
@@ -24,6 +85,10 @@ pub trait IOBackend {
     fn update_existing_lesson(&self, id: Id, lesson: &LessonInfo) -> Result<(), Self::Error>;
 
     fn remove_lesson(&self, id: Id) -> Result<(), Self::Error>;
+
+    fn query_reviews(&self) -> Result<Vec<Review>, Self::Error>;
+
+    fn add_review(&self, review: &Review) -> Result<(), Self::Error>;
 }
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, Default)]
@@ -48,12 +113,7 @@ impl LessonStatus {
     /// a random amount of days that this lesson is going to be good for.
     pub fn new_status_if_studied<R: Rng + ?Sized>(new_level: u32, rng: &mut R) -> Self {
         let today = chrono::offset::Local::now().date_naive();
-        let basic_day = days_from_level(new_level);
-        let diff = (0.1 * basic_day as f64) as u64;
-        let min_day = basic_day - diff;
-        let max_day = basic_day + diff;
-
-        let days_to_next = rng.gen_range(min_day..=max_day);
+        let days_to_next = grade::vary_interval(days_from_level(new_level), rng);
 
         let good_until = today + Days::new(days_to_next);
 
@@ -65,6 +125,25 @@ impl LessonStatus {
     }
 }
 
+impl LessonStatus {
+    /// How late the lesson is on `today`, relative to the interval it was given: a lesson 3 days
+    /// late on a 2 day interval is 1.5 overdue. It is negative for lessons that are not due yet,
+    /// and `None` for lessons without a due date.
+    pub fn overdueness(&self, today: NaiveDate) -> Option<f64> {
+        match self {
+            LessonStatus::Practiced {
+                last_practiced,
+                good_until,
+                ..
+            } => {
+                let interval = std::cmp::max((*good_until - *last_practiced).num_days(), 1);
+                Some((today - *good_until).num_days() as f64 / interval as f64)
+            }
+            LessonStatus::NotPracticed | LessonStatus::GoodEnough => None,
+        }
+    }
+}
+
 impl LessonStatus {
     /// Whether or not a lesson is considered "known", irrespective of its prerequisites's status
     fn needs_work(&self) -> bool {
@@ -99,13 +178,66 @@ pub enum NodeStatus {
 
 /// A struct to represent a lesson in the app. Contains every information about the lesson that we
 /// know.
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
 pub struct LessonInfo {
     pub name: String,
     /// The list of all prerequisite lessons, identified by their `id`.
     pub direct_prerequisites: Vec<Id>,
     pub status: LessonStatus,
     pub tags: Vec<String>,
+    /// How many times the lesson was forgotten after having been practiced.
+    pub lapses: u32,
+    pub priority: Priority,
+    /// The date by which this lesson needs to be known, if it is a goal.
+    pub deadline: Option<NaiveDate>,
+    /// Identifies the lesson across every copy of the lessons. It is given when the lesson is
+    /// added to a `Graph`, and never changes afterwards.
+    pub uid: Uid,
+}
+
+/// How important it is to study a lesson, compared to the others. This is set by the user.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, Default)]
+pub enum Priority {
+    Low,
+    #[default]
+    Normal,
+    High,
+}
+
+impl Priority {
+    pub fn raised(self) -> Self {
+        match self {
+            Priority::Low => Priority::Normal,
+            Priority::Normal | Priority::High => Priority::High,
+        }
+    }
+
+    pub fn lowered(self) -> Self {
+        match self {
+            Priority::High => Priority::Normal,
+            Priority::Normal | Priority::Low => Priority::Low,
+        }
+    }
+}
+
+/// A past study session of a lesson.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Review {
+    /// The `id` of the lesson that was studied. The lesson may have been deleted since.
+    pub lesson: Id,
+    pub date: NaiveDate,
+    /// The status the lesson was given at the end of the session.
+    pub status: LessonStatus,
+    /// How the session went, if it was graded rather than the status being chosen by hand.
+    pub grade: Option<Grade>,
+    /// How long the session lasted, if it was timed.
+    pub duration: Option<Duration>,
+    /// Identifies the review across every copy of the lessons, so that identical sessions are
+    /// still told apart when merging them. Reviews recorded before they had one have the nil
+    /// `Uid`.
+    #[serde(default)]
+    pub uid: Uid,
 }
 
 /// A runtime node of the graph structure. Contains a lesson and additional runtime info.
@@ -127,6 +259,8 @@ pub struct Graph<T: IOBackend> {
     children: HashMap<Id, Vec<Id>>,
     /// the next id to give to a newly created node.
     next_id: Id,
+    /// every review ever made, oldest first.
+    reviews: Vec<Review>,
 
     io_backend: T,
 }
@@ -137,6 +271,11 @@ pub enum BuissonError<T: IOBackend> {
     MissingId(Id),
     /// there was an error in the communication with the backend
     BackendError(T::Error),
+    /// merging the two lessons would make the merged lesson depend on itself.
+    WouldCreateCycle(Id, Id),
+    /// a part of a split lesson was given a lesson that is neither a prerequisite nor a dependent
+    /// of it.
+    InvalidSplit(Id),
 }
 
 impl<T: IOBackend> std::fmt::Display for BuissonError<T> {
@@ -146,6 +285,14 @@ impl<T: IOBackend> std::fmt::Display for BuissonError<T> {
             BuissonError::BackendError(e) => {
                 writeln!(f, "Error while interacting with the backend : {:?}", e)
             }
+            BuissonError::WouldCreateCycle(id, other) => writeln!(
+                f,
+                "Merging {id} and {other} would make a lesson depend on itself."
+            ),
+            BuissonError::InvalidSplit(id) => writeln!(
+                f,
+                "The lesson {id} is neither a prerequisite nor a dependent of the split lesson."
+            ),
         }
     }
 }
@@ -154,9 +301,12 @@ impl<T: IOBackend> Graph<T> {
     /// create a new node in the graph, and update the relevant data structures inside. This is a
     /// public facing function, and should be able to be called without altering the correctness of
     /// the state of `self`. It also returns the Id of the newly created node.
-    pub fn create_new_node(&mut self, lesson_info: LessonInfo) -> Result<Id, BuissonError<T>> {
+    pub fn create_new_node(&mut self, mut lesson_info: LessonInfo) -> Result<Id, BuissonError<T>> {
         let id = self.next_id;
         self.next_id += 1;
+        if lesson_info.uid.is_nil() {
+            lesson_info.uid = Uid::random(&mut thread_rng());
+        }
 
         for &parent in &lesson_info.direct_prerequisites {
             // ok to unwrap here, if there is an error, it means the internal state was invalid to
@@ -219,6 +369,45 @@ impl<T: IOBackend> Graph<T> {
         Ok(())
     }
 
+    /// Delete node with id `id` like `delete_node`, but make the lessons depending on it depend on
+    /// its prerequisites instead, so that they keep requiring them. Prerequisites a lesson already
+    /// requires through its other prerequisites are not added to it.
+    pub fn delete_node_reconnecting(&mut self, id: Id) -> Result<(), BuissonError<T>> {
+        let prerequisites = self.get(id)?.lesson.direct_prerequisites.clone();
+
+        // the new prerequisites are computed before anything changes. Requirements going through
+        // `id` still hold afterwards, as every lesson depending on it is reconnected.
+        let mut new_children = vec![];
+        for &child_id in self.get_children(id)? {
+            let mut lesson = self.get(child_id)?.lesson.clone();
+            lesson.direct_prerequisites.retain(|&parent| parent != id);
+            let candidates = lesson
+                .direct_prerequisites
+                .iter()
+                .chain(&prerequisites)
+                .copied()
+                .collect::<Vec<_>>();
+            for &prerequisite in &prerequisites {
+                let mut implied = false;
+                for &other in &candidates {
+                    if other != prerequisite && self.depends_on(other, prerequisite)? {
+                        implied = true;
+                        break;
+                    }
+                }
+                if !implied && !lesson.direct_prerequisites.contains(&prerequisite) {
+                    lesson.direct_prerequisites.push(prerequisite);
+                }
+            }
+            new_children.push((child_id, lesson));
+        }
+
+        for (child_id, lesson) in new_children {
+            self.edit_node(child_id, lesson)?;
+        }
+        self.delete_node(id)
+    }
+
     /// Compute the runtime status of the node with id `id` in the graph, and updates the current
     /// value. If the value has changed, calls itself on the children of the node whose status we
     /// just modified, as their status depends on the status of `id`.
@@ -265,11 +454,108 @@ impl<T: IOBackend> Graph<T> {
         self.nodes.get_mut(&id).unwrap().lesson.direct_prerequisites =
             lesson_info.direct_prerequisites;
         self.nodes.get_mut(&id).unwrap().lesson.status = lesson_info.status;
+        self.nodes.get_mut(&id).unwrap().lesson.tags = lesson_info.tags;
+        self.nodes.get_mut(&id).unwrap().lesson.lapses = lesson_info.lapses;
+        self.nodes.get_mut(&id).unwrap().lesson.priority = lesson_info.priority;
+        self.nodes.get_mut(&id).unwrap().lesson.deadline = lesson_info.deadline;
 
         self.update_node_status(id)?;
         Ok(())
     }
 
+    /// Give lesson `id` the status `status` after it was studied today, and record the review in
+    /// the history. `grade` is how the session went, if it was graded, and `duration` how long it
+    /// took, if it was timed. Failed sessions count as a lapse of the lesson. Lessons needed for a
+    /// goal are made due before its deadline.
+    pub fn study(
+        &mut self,
+        id: Id,
+        status: LessonStatus,
+        grade: Option<Grade>,
+        duration: Option<Duration>,
+    ) -> Result<(), BuissonError<T>> {
+        let today = chrono::offset::Local::now().date_naive();
+        let status = match self.deadline_for(id, today) {
+            Some(deadline) => goal::cap_before_deadline(status, deadline, today),
+            None => status,
+        };
+
+        let old_lesson = &self.get(id)?.lesson;
+        let lapses = if grade::is_lapse(&old_lesson.status, &status, grade) {
+            old_lesson.lapses + 1
+        } else {
+            old_lesson.lapses
+        };
+        let lesson = LessonInfo {
+            status,
+            lapses,
+            ..old_lesson.clone()
+        };
+        self.edit_node(id, lesson)?;
+
+        let review = Review {
+            lesson: id,
+            date: today,
+            status,
+            grade,
+            duration,
+            uid: Uid::random(&mut thread_rng()),
+        };
+        self.io_backend
+            .add_review(&review)
+            .map_err(BuissonError::BackendError)?;
+        self.reviews.push(review);
+        Ok(())
+    }
+
+    /// Return the direct prerequisites of lesson `id` that could be reviewed early: the ones that
+    /// have been practiced and are not due yet.
+    pub fn early_review_candidates(&self, id: Id) -> Result<Vec<Id>, BuissonError<T>> {
+        let today = chrono::offset::Local::now().date_naive();
+        let candidates = self
+            .get(id)?
+            .lesson
+            .direct_prerequisites
+            .iter()
+            .copied()
+            .filter(|prereq| match self.nodes[prereq].lesson.status {
+                LessonStatus::Practiced { good_until, .. } => good_until > today,
+                LessonStatus::NotPracticed | LessonStatus::GoodEnough => false,
+            })
+            .collect();
+        Ok(candidates)
+    }
+
+    /// Make lesson `id` due today, keeping its level. This is used when a lesson depending on it
+    /// was failed, which hints that it may have been forgotten too. Lessons that are not
+    /// practiced are left untouched.
+    pub fn review_early(&mut self, id: Id) -> Result<(), BuissonError<T>> {
+        let today = chrono::offset::Local::now().date_naive();
+        let lesson = &self.get(id)?.lesson;
+        if let LessonStatus::Practiced {
+            level,
+            last_practiced,
+            good_until,
+        } = lesson.status
+        {
+            let lesson = LessonInfo {
+                status: LessonStatus::Practiced {
+                    level,
+                    last_practiced,
+                    good_until: std::cmp::min(good_until, today),
+                },
+                ..lesson.clone()
+            };
+            self.edit_node(id, lesson)?;
+        }
+        Ok(())
+    }
+
+    /// Return every review ever made, oldest first.
+    pub fn reviews(&self) -> &[Review] {
+        &self.reviews
+    }
+
     /// Return the id of a lesson chosen uniformly among all pending lessons. In case there are
     /// no pending lessons, returns `None`.
     pub fn random_pending<R: Rng + ?Sized>(&self, rng: &mut R) -> Option<Id> {
@@ -280,10 +566,14 @@ impl<T: IOBackend> Graph<T> {
             .map(|(id, _)| *id)
     }
 
-    /// Return an iterator with only the lessons whose name contain the string `search_request`.
-    pub fn perform_search(&self, search_request: String) -> impl Iterator<Item = &GraphNode> {
-        self.lessons_iter()
-            .filter(move |&node| node.lesson.name.contains(&search_request))
+    /// Return the ids of the lessons matching `search_request`, best matches first, along with
+    /// how their name matched. See the `query` module for the syntax of requests.
+    pub fn perform_search(
+        &self,
+        search_request: &str,
+    ) -> Result<Vec<(Id, FuzzyMatch)>, QueryParseError> {
+        let query = search_request.parse::<Query>()?;
+        Ok(query.search(&self.nodes, self.nodes.keys().copied()))
     }
 
     /// this function is called when the statuses of all the prereqs have been computed. It
@@ -319,7 +609,15 @@ impl<T: IOBackend> Graph<T> {
     /// program.
     pub fn get_from_database(backend: T) -> Result<Self, T::Error> {
         let builder = GraphBuilder::load_from_database(backend)?;
-        let ret = builder.into_graph();
+        let mut ret = builder.into_graph();
+
+        // lessons created before uids existed get one now
+        for (&id, node) in ret.nodes.iter_mut() {
+            if node.lesson.uid.is_nil() {
+                node.lesson.uid = Uid::random(&mut thread_rng());
+                ret.io_backend.update_existing_lesson(id, &node.lesson)?;
+            }
+        }
         Ok(ret)
     }
 
@@ -404,6 +702,12 @@ impl<T: IOBackend> Graph<T> {
         Ok(false)
     }
 
+    /// Return the map between ids and the ids of the lessons that have them as a direct
+    /// prerequisite.
+    pub fn children(&self) -> &HashMap<Id, Vec<Id>> {
+        &self.children
+    }
+
     /// return the ids of the lessons that have `id` as a prerequisite.
     pub fn get_children(&self, id: Id) -> Result<&[Id], BuissonError<T>> {
         Ok(self.children.get(&id).ok_or(BuissonError::MissingId(id))?)
@@ -417,6 +721,7 @@ impl<T: IOBackend> Graph<T> {
 #[derive(Debug, Default)]
 struct GraphBuilder<Backend: IOBackend> {
     lessons: HashMap<Id, (LessonInfo, Option<NodeStatus>)>,
+    reviews: Vec<Review>,
     backend: Backend,
 }
 
@@ -461,6 +766,7 @@ impl<Backend: IOBackend> GraphBuilder<Backend> {
                 })
                 .collect(),
             children,
+            reviews: self.reviews,
             io_backend: self.backend,
         }
     }
@@ -468,11 +774,14 @@ impl<Backend: IOBackend> GraphBuilder<Backend> {
     /// Retrieve the data from the database in `backend`
     fn load_from_database(backend: Backend) -> Result<Self, Backend::Error> {
         let lessons = backend.query_lessons()?;
+        let mut reviews = backend.query_reviews()?;
+        reviews.sort_by_key(|review| review.date);
         Ok(Self {
             lessons: lessons
                 .into_iter()
                 .map(|(id, lesson)| (id, (lesson, None)))
                 .collect(),
+            reviews,
             backend,
         })
     }
@@ -553,6 +862,14 @@ mod tests {
         fn remove_lesson(&self, _id: Id) -> Result<(), Self::Error> {
             Ok(())
         }
+
+        fn query_reviews(&self) -> Result<Vec<Review>, Self::Error> {
+            Ok(vec![])
+        }
+
+        fn add_review(&self, _review: &Review) -> Result<(), Self::Error> {
+            Ok(())
+        }
     }
 
     fn test_dummy_backend() -> DummyIOBackend {
@@ -562,30 +879,35 @@ mod tests {
                 direct_prerequisites: vec![1],
                 status: LessonStatus::NotPracticed,
                 tags: vec![],
+                ..Default::default()
             },
             LessonInfo {
                 name: String::from("Test 1"),
                 direct_prerequisites: vec![],
                 status: LessonStatus::GoodEnough,
                 tags: vec![],
+                ..Default::default()
             },
             LessonInfo {
                 name: String::from("Test 2"),
                 direct_prerequisites: vec![1, 0, 3],
                 status: LessonStatus::GoodEnough,
                 tags: vec![],
+                ..Default::default()
             },
             LessonInfo {
                 name: String::from("Test 3"),
                 direct_prerequisites: vec![0],
                 status: LessonStatus::NotPracticed,
                 tags: vec![],
+                ..Default::default()
             },
             LessonInfo {
                 name: String::from("Test 4"),
                 direct_prerequisites: vec![2],
                 status: LessonStatus::NotPracticed,
                 tags: vec![],
+                ..Default::default()
             },
         ];
 
@@ -645,6 +967,7 @@ mod tests {
                     direct_prerequisites: vec![1],
                     status: LessonStatus::NotPracticed,
                     tags: vec![],
+                    ..Default::default()
                 },
                 status: NodeStatus::Pending,
             },
@@ -654,6 +977,7 @@ mod tests {
                     direct_prerequisites: vec![],
                     status: LessonStatus::GoodEnough,
                     tags: vec![],
+                    ..Default::default()
                 },
                 status: NodeStatus::Ok,
             },
@@ -663,6 +987,7 @@ mod tests {
                     direct_prerequisites: vec![1, 0, 3],
                     status: LessonStatus::GoodEnough,
                     tags: vec![],
+                    ..Default::default()
                 },
                 status: NodeStatus::Ok,
             },
@@ -672,6 +997,7 @@ mod tests {
                     direct_prerequisites: vec![0],
                     status: LessonStatus::NotPracticed,
                     tags: vec![],
+                    ..Default::default()
                 },
                 status: NodeStatus::MissingPrereq(vec![0]),
             },
@@ -681,6 +1007,7 @@ mod tests {
                     direct_prerequisites: vec![2],
                     status: LessonStatus::NotPracticed,
                     tags: vec![],
+                    ..Default::default()
                 },
                 status: NodeStatus::Pending,
             },
@@ -708,6 +1035,7 @@ mod tests {
                     direct_prerequisites: vec![1],
                     status: LessonStatus::NotPracticed,
                     tags: vec![],
+                    ..Default::default()
                 },
                 status: NodeStatus::Pending,
             },
@@ -717,6 +1045,7 @@ mod tests {
                     direct_prerequisites: vec![],
                     status: LessonStatus::GoodEnough,
                     tags: vec![],
+                    ..Default::default()
                 },
                 status: NodeStatus::Ok,
             },
@@ -726,6 +1055,7 @@ mod tests {
                     direct_prerequisites: vec![1, 0, 3],
                     status: LessonStatus::GoodEnough,
                     tags: vec![],
+                    ..Default::default()
                 },
                 status: NodeStatus::Ok,
             },
@@ -735,6 +1065,7 @@ mod tests {
                     direct_prerequisites: vec![0],
                     status: LessonStatus::NotPracticed,
                     tags: vec![],
+                    ..Default::default()
                 },
                 status: NodeStatus::MissingPrereq(vec![0]),
             },
@@ -744,6 +1075,7 @@ mod tests {
                     direct_prerequisites: vec![2],
                     status: LessonStatus::NotPracticed,
                     tags: vec![],
+                    ..Default::default()
                 },
                 status: NodeStatus::Pending,
             },
@@ -753,6 +1085,7 @@ mod tests {
                     direct_prerequisites: vec![2],
                     status: LessonStatus::NotPracticed,
                     tags: vec![],
+                    ..Default::default()
                 },
                 status: NodeStatus::Pending,
             },
@@ -762,6 +1095,7 @@ mod tests {
                     direct_prerequisites: vec![5, 2],
                     status: LessonStatus::NotPracticed,
                     tags: vec![],
+                    ..Default::default()
                 },
                 status: NodeStatus::MissingPrereq(vec![5]),
             },
@@ -772,6 +1106,7 @@ mod tests {
             direct_prerequisites: vec![2],
             status: LessonStatus::NotPracticed,
             tags: vec![],
+            ..Default::default()
         })
         .unwrap();
 
@@ -780,6 +1115,7 @@ mod tests {
             direct_prerequisites: vec![5, 2],
             status: LessonStatus::NotPracticed,
             tags: vec![],
+            ..Default::default()
         })
         .unwrap();
 
@@ -805,6 +1141,7 @@ mod tests {
                     direct_prerequisites: vec![],
                     status: LessonStatus::GoodEnough,
                     tags: vec![],
+                    ..Default::default()
                 },
                 status: NodeStatus::Ok,
             },
@@ -814,6 +1151,7 @@ mod tests {
                     direct_prerequisites: vec![],
                     status: LessonStatus::GoodEnough,
                     tags: vec![],
+                    ..Default::default()
                 },
                 status: NodeStatus::Ok,
             },
@@ -823,6 +1161,7 @@ mod tests {
                     direct_prerequisites: vec![1, 0, 3],
                     status: LessonStatus::GoodEnough,
                     tags: vec![],
+                    ..Default::default()
                 },
                 status: NodeStatus::Ok,
             },
@@ -832,6 +1171,7 @@ mod tests {
                     direct_prerequisites: vec![0],
                     status: LessonStatus::NotPracticed,
                     tags: vec![],
+                    ..Default::default()
                 },
                 status: NodeStatus::Pending,
             },
@@ -841,6 +1181,7 @@ mod tests {
                     direct_prerequisites: vec![2],
                     status: LessonStatus::NotPracticed,
                     tags: vec![],
+                    ..Default::default()
                 },
                 status: NodeStatus::Pending,
             },
@@ -853,6 +1194,7 @@ mod tests {
                 direct_prerequisites: vec![],
                 status: LessonStatus::GoodEnough,
                 tags: vec![],
+                ..Default::default()
             },
         )
         .unwrap();
@@ -865,4 +1207,403 @@ mod tests {
 
         assert_eq!(g.nodes, nodes);
     }
+
+    #[test]
+    fn test_delete_reconnecting() {
+        let mut g = Graph::get_from_database(test_dummy_backend()).unwrap();
+
+        // 1 and 0 are required by 3 already
+        g.delete_node_reconnecting(2).unwrap();
+        assert_eq!(g.get(4).unwrap().lesson.direct_prerequisites, vec![3]);
+        assert_eq!(g.get_children(3).unwrap(), &[4]);
+        assert_eq!(g.get(4).unwrap().status, NodeStatus::MissingPrereq(vec![3]));
+
+        g.delete_node_reconnecting(3).unwrap();
+        assert_eq!(g.get(4).unwrap().lesson.direct_prerequisites, vec![0]);
+
+        g.delete_node(0).unwrap();
+        assert!(g.get(4).unwrap().lesson.direct_prerequisites.is_empty());
+    }
+
+    #[test]
+    fn test_merging() {
+        let mut backend = test_dummy_backend();
+        backend.lessons.get_mut(&0).unwrap().tags = vec![String::from("a")];
+        backend.lessons.get_mut(&3).unwrap().tags = vec![String::from("b"), String::from("a")];
+        backend.lessons.get_mut(&3).unwrap().status = LessonStatus::GoodEnough;
+        let mut g = Graph::get_from_database(backend).unwrap();
+
+        // 2 depends on 0 through 3
+        assert!(!g.can_merge(2, 0).unwrap());
+        assert!(g.merge_nodes(2, 0).is_err());
+
+        g.merge_nodes(3, 0).unwrap();
+        assert!(g.get(0).is_err());
+        let merged = &g.get(3).unwrap().lesson;
+        assert_eq!(merged.direct_prerequisites, vec![1]);
+        assert_eq!(merged.tags, vec![String::from("b"), String::from("a")]);
+        assert_eq!(merged.status, LessonStatus::NotPracticed);
+        assert_eq!(g.get(2).unwrap().lesson.direct_prerequisites, vec![1, 3]);
+        assert_eq!(g.get_children(3).unwrap(), &[2]);
+    }
+
+    #[test]
+    fn test_splitting() {
+        use restructure::SplitPart;
+
+        let mut g = Graph::get_from_database(test_dummy_backend()).unwrap();
+
+        let invalid = SplitPart {
+            name: String::from("Invalid"),
+            prerequisites: vec![4],
+            dependents: vec![],
+        };
+        assert!(g.split_node(2, vec![invalid]).is_err());
+
+        let parts = vec![
+            SplitPart {
+                name: String::from("Test 2a"),
+                prerequisites: vec![1],
+                dependents: vec![],
+            },
+            SplitPart {
+                name: String::from("Test 2b"),
+                prerequisites: vec![0, 3],
+                dependents: vec![4],
+            },
+        ];
+        assert_eq!(g.split_node(2, parts).unwrap(), vec![2, 5]);
+        assert_eq!(g.get(2).unwrap().lesson.name, "Test 2a");
+        assert_eq!(g.get(2).unwrap().lesson.direct_prerequisites, vec![1]);
+        assert_eq!(g.get(5).unwrap().lesson.direct_prerequisites, vec![0, 3]);
+        assert_eq!(g.get(4).unwrap().lesson.direct_prerequisites, vec![5]);
+        assert!(g.get_children(2).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_redundant_prerequisites() {
+        use redundant::RedundantPrerequisite;
+
+        let mut g = Graph::get_from_database(test_dummy_backend()).unwrap();
+
+        // 2 requires 3, which requires 0, which requires 1
+        let redundant = vec![
+            RedundantPrerequisite {
+                prerequisite: 1,
+                through: 0,
+            },
+            RedundantPrerequisite {
+                prerequisite: 0,
+                through: 3,
+            },
+        ];
+        assert_eq!(g.redundant_prerequisites(2).unwrap(), redundant);
+        assert!(g.redundant_prerequisites(4).unwrap().is_empty());
+        assert_eq!(
+            g.all_redundant_prerequisites(),
+            vec![(2, redundant.clone())]
+        );
+
+        assert_eq!(
+            g.remove_redundant_prerequisites().unwrap(),
+            vec![(2, redundant)]
+        );
+        assert_eq!(g.get(2).unwrap().lesson.direct_prerequisites, vec![3]);
+        assert!(g.depends_on(2, 1).unwrap());
+        assert!(g.all_redundant_prerequisites().is_empty());
+    }
+
+    #[test]
+    fn test_sorting() {
+        use sort::SortOrder;
+
+        let g = Graph::get_from_database(test_dummy_backend()).unwrap();
+
+        assert_eq!(g.sorted_ids(SortOrder::Name), vec![0, 1, 2, 3, 4]);
+        assert_eq!(g.sorted_ids(SortOrder::Status), vec![0, 4, 3, 1, 2]);
+        assert_eq!(g.sorted_ids(SortOrder::Dependents), vec![1, 0, 3, 2, 4]);
+        assert_eq!(g.sorted_ids(SortOrder::Topological), vec![1, 0, 3, 2, 4]);
+    }
+
+    #[test]
+    fn test_search() {
+        let mut backend = test_dummy_backend();
+        backend.lessons.get_mut(&3).unwrap().tags = vec![String::from("Math")];
+        backend.lessons.get_mut(&4).unwrap().tags = vec![String::from("math")];
+
+        let g = Graph::get_from_database(backend).unwrap();
+
+        let search = |request: &str| {
+            let mut ids = g
+                .perform_search(request)
+                .unwrap()
+                .into_iter()
+                .map(|(id, _)| id)
+                .collect::<Vec<_>>();
+            ids.sort();
+            ids
+        };
+
+        assert_eq!(search("tag:math"), vec![3, 4]);
+        assert_eq!(search("tag:math status:red"), vec![3]);
+        assert_eq!(search("requires:\"test 0\""), vec![2, 3, 4]);
+        assert_eq!(search("requires:\"test 0\" -tag:math"), vec![2]);
+        assert_eq!(search("status:pending blockers=0 4"), vec![4]);
+        assert!(g.perform_search("level>=high").is_err());
+    }
+
+    #[test]
+    fn test_lapses() {
+        use grade::Grade;
+
+        let mut g = Graph::get_from_database(test_dummy_backend()).unwrap();
+        let today = chrono::offset::Local::now().date_naive();
+        let practiced = |level| LessonStatus::Practiced {
+            level,
+            last_practiced: today,
+            good_until: today + Days::new(1),
+        };
+
+        g.study(4, practiced(3), Some(Grade::Good), None).unwrap();
+        g.study(4, practiced(1), Some(Grade::Again), None).unwrap();
+        g.study(4, practiced(0), None, None).unwrap();
+        g.study(4, practiced(0), Some(Grade::Again), None).unwrap();
+        g.study(4, practiced(1), None, None).unwrap();
+        assert_eq!(g.get(4).unwrap().lesson.lapses, 3);
+
+        // studying a lesson for the first time can't be a lapse, whatever the grade
+        g.study(3, practiced(0), Some(Grade::Again), None).unwrap();
+        assert_eq!(g.get(3).unwrap().lesson.lapses, 0);
+        g.study(1, practiced(0), Some(Grade::Again), None).unwrap();
+        assert_eq!(g.get(1).unwrap().lesson.lapses, 0);
+
+        let leeches = g.perform_search("lapses>=3").unwrap();
+        assert_eq!(
+            leeches.into_iter().map(|(id, _)| id).collect::<Vec<_>>(),
+            vec![4]
+        );
+    }
+
+    #[test]
+    fn test_statistics() {
+        let mut backend = test_dummy_backend();
+        backend.lessons.get_mut(&4).unwrap().tags = vec![String::from("math")];
+        let mut g = Graph::get_from_database(backend).unwrap();
+        let today = chrono::offset::Local::now().date_naive();
+
+        g.study(
+            4,
+            LessonStatus::Practiced {
+                level: 2,
+                last_practiced: today,
+                good_until: today + Days::new(15),
+            },
+            None,
+            Some(Duration::from_secs(300)),
+        )
+        .unwrap();
+        let stats = g.statistics(today);
+
+        assert_eq!(stats.levels, [(2, 1)].into_iter().collect());
+        assert_eq!(
+            (stats.not_practiced, stats.good_enough, stats.practiced),
+            (2, 2, 1)
+        );
+        assert_eq!((stats.ok, stats.pending, stats.missing_prereq), (3, 1, 1));
+        assert_eq!(stats.reviews_per_day.last(), Some(&(today, 1)));
+        assert_eq!((stats.current_streak, stats.longest_streak), (1, 1));
+        assert_eq!(stats.most_overdue, vec![]);
+        assert_eq!(stats.most_blocking, vec![(0, 1)]);
+        assert_eq!(stats.time_per_lesson, vec![(4, Duration::from_secs(300))]);
+        assert_eq!(
+            stats.time_per_tag,
+            vec![(String::from("math"), Duration::from_secs(300))]
+        );
+        assert_eq!(stats.average_review_time, Some(Duration::from_secs(300)));
+        // only lesson 0 is pending, and it was never timed
+        assert_eq!(stats.todays_queue_time, Some(Duration::from_secs(300)));
+    }
+
+    #[test]
+    fn test_grading() {
+        use grade::Grade;
+
+        let today = chrono::offset::Local::now().date_naive();
+        let practiced = LessonStatus::Practiced {
+            level: 3,
+            last_practiced: today,
+            good_until: today,
+        };
+
+        let levels = Grade::ALL.map(|grade| practiced.level_after(grade));
+        assert_eq!(levels, [Some(1), Some(3), Some(4), Some(5)]);
+        let intervals = Grade::ALL.map(|grade| practiced.interval_after(grade));
+        assert_eq!(intervals, [Some(1), Some(15), Some(60), Some(120)]);
+
+        let levels = Grade::ALL.map(|grade| LessonStatus::NotPracticed.level_after(grade));
+        assert_eq!(levels, [Some(0), Some(0), Some(0), Some(1)]);
+
+        // a known lesson that was remembered stays known
+        let levels = Grade::ALL.map(|grade| LessonStatus::GoodEnough.level_after(grade));
+        assert_eq!(levels, [Some(0), None, None, None]);
+        for grade in [Grade::Hard, Grade::Good] {
+            let status =
+                LessonStatus::GoodEnough.new_status_if_graded(grade, &mut rand::thread_rng());
+            assert!(matches!(status, LessonStatus::GoodEnough));
+        }
+
+        let status = practiced.new_status_if_graded(Grade::Again, &mut rand::thread_rng());
+        assert!(matches!(
+            status,
+            LessonStatus::Practiced { level: 1, good_until, .. } if good_until == today + Days::new(1)
+        ));
+    }
+
+    #[test]
+    fn test_early_review() {
+        let mut backend = test_dummy_backend();
+        let today = chrono::offset::Local::now().date_naive();
+        backend.lessons.get_mut(&0).unwrap().status = LessonStatus::Practiced {
+            level: 2,
+            last_practiced: today,
+            good_until: today + Days::new(10),
+        };
+        let mut g = Graph::get_from_database(backend).unwrap();
+
+        assert_eq!(g.get(3).unwrap().status, NodeStatus::Pending);
+        assert_eq!(g.early_review_candidates(3).unwrap(), vec![0]);
+        assert_eq!(g.early_review_candidates(4).unwrap(), vec![]);
+
+        g.review_early(0).unwrap();
+        assert_eq!(g.get(0).unwrap().status, NodeStatus::Pending);
+        assert_eq!(g.get(3).unwrap().status, NodeStatus::MissingPrereq(vec![0]));
+        assert_eq!(g.early_review_candidates(3).unwrap(), vec![]);
+    }
+
+    #[test]
+    fn test_recommendations() {
+        let today = chrono::offset::Local::now().date_naive();
+        let g = Graph::get_from_database(test_dummy_backend()).unwrap();
+
+        let recommendations = g.recommendations(today);
+        let unlocks = recommendations
+            .iter()
+            .map(|r| (r.id, r.unlocks))
+            .collect::<Vec<_>>();
+        assert_eq!(unlocks, vec![(0, 1), (4, 0)]);
+        assert_eq!(g.recommend_next(today), Some(0));
+
+        let mut backend = test_dummy_backend();
+        backend.lessons.get_mut(&0).unwrap().priority = Priority::Low;
+        backend.lessons.get_mut(&4).unwrap().priority = Priority::High;
+        let g = Graph::get_from_database(backend).unwrap();
+        assert_eq!(g.recommend_next(today), Some(4));
+
+        let important = g.perform_search("priority>=normal").unwrap();
+        assert_eq!(important.len(), 4);
+    }
+
+    #[test]
+    fn test_learning_path() {
+        let mut backend = test_dummy_backend();
+        backend.lessons.get_mut(&2).unwrap().status = LessonStatus::NotPracticed;
+        let g = Graph::get_from_database(backend).unwrap();
+
+        assert_eq!(g.learning_path(4).unwrap(), vec![0, 3, 2, 4]);
+        assert_eq!(g.learning_path(3).unwrap(), vec![0, 3]);
+        assert_eq!(g.learning_path(1).unwrap(), vec![]);
+        assert_eq!(path::days_to_study(4, 3), 2);
+    }
+
+    #[test]
+    fn test_goals() {
+        let today = chrono::offset::Local::now().date_naive();
+        let mut backend = test_dummy_backend();
+        backend.lessons.get_mut(&3).unwrap().deadline = Some(today + Days::new(1));
+        backend.lessons.get_mut(&4).unwrap().deadline = Some(today - Days::new(1));
+        let mut g = Graph::get_from_database(backend).unwrap();
+
+        let goals = g.goals(today, 1).unwrap();
+        assert_eq!(goals.len(), 1);
+        assert_eq!(
+            (goals[0].id, goals[0].remaining, goals[0].needed_per_day),
+            (3, 2, 2)
+        );
+        assert!(goals[0].at_risk);
+        assert!(!g.goals(today, 2).unwrap()[0].at_risk);
+
+        assert_eq!(g.deadline_for(0, today), Some(today + Days::new(1)));
+        assert_eq!(g.deadline_for(4, today), None);
+
+        let in_ten_days = today + Days::new(10);
+        let status = |good_until| LessonStatus::Practiced {
+            level: 2,
+            last_practiced: today,
+            good_until,
+        };
+        let capped = goal::cap_before_deadline(status(today + Days::new(15)), in_ten_days, today);
+        assert!(
+            matches!(capped, LessonStatus::Practiced { good_until, .. } if good_until == today + Days::new(9))
+        );
+        let kept = goal::cap_before_deadline(status(today + Days::new(5)), in_ten_days, today);
+        assert!(
+            matches!(kept, LessonStatus::Practiced { good_until, .. } if good_until == today + Days::new(5))
+        );
+
+        g.study(0, status(today + Days::new(15)), None, None)
+            .unwrap();
+        assert!(
+            matches!(g.get(0).unwrap().lesson.status, LessonStatus::Practiced { good_until, .. } if good_until == today + Days::new(15))
+        );
+    }
+
+    #[test]
+    fn test_calendar_export() {
+        use calendar::CalendarEvents;
+
+        let today = chrono::offset::Local::now().date_naive();
+        let practiced = |good_until| LessonStatus::Practiced {
+            level: 1,
+            last_practiced: today,
+            good_until,
+        };
+        let mut backend = test_dummy_backend();
+        backend.lessons.get_mut(&0).unwrap().status = practiced(today + Days::new(3));
+        backend.lessons.get_mut(&3).unwrap().status = practiced(today - Days::new(2));
+        backend.lessons.get_mut(&4).unwrap().status = practiced(today + Days::new(3));
+        let g = Graph::get_from_database(backend).unwrap();
+
+        assert_eq!(
+            g.due_lessons_per_day(today),
+            [(today, vec![3]), (today + Days::new(3), vec![0, 4])]
+                .into_iter()
+                .collect()
+        );
+
+        let stamp = today.and_hms_opt(12, 0, 0).unwrap();
+        let per_day = g.to_icalendar(today, stamp, CalendarEvents::PerDay);
+        assert_eq!(per_day.matches("BEGIN:VEVENT").count(), 2);
+        assert!(per_day.contains("SUMMARY:2 lessons to review\r\nDESCRIPTION:Test 0\\nTest 4\r\n"));
+        assert!(per_day.lines().all(|line| line.len() <= 75));
+
+        let per_lesson = g.to_icalendar(today, stamp, CalendarEvents::PerLesson);
+        assert_eq!(per_lesson.matches("BEGIN:VEVENT").count(), 3);
+        assert!(per_lesson.contains("SUMMARY:Review Test 3\r\n"));
+    }
+
+    #[test]
+    fn test_uids() {
+        let mut g = Graph::get_from_database(test_dummy_backend()).unwrap();
+        let id = g.create_new_node(LessonInfo::default()).unwrap();
+
+        let uids = g
+            .lessons_iter()
+            .map(|node| node.lesson.uid)
+            .collect::<std::collections::HashSet<_>>();
+        assert_eq!(uids.len(), 6);
+        assert!(uids.iter().all(|uid| !uid.is_nil()));
+
+        let uid = g.get(id).unwrap().lesson.uid;
+        assert_eq!(uid.to_string().parse::<Uid>(), Ok(uid));
+    }
 }
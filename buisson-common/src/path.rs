@@ -0,0 +1,34 @@
+//! Planning the study of a lesson that is not reachable yet.
+
+use std::collections::HashSet;
+
+use crate::{BuissonError, Graph, IOBackend, Id, NodeStatus};
+
+impl<T: IOBackend> Graph<T> {
+    /// Return every lesson to study before `target` is `Ok`, ending with `target` itself if it
+    /// needs work, in an order where prerequisites come first. These are the ancestors of
+    /// `target` that are not `Ok`: the prerequisites of an `Ok` lesson don't need to be studied.
+    pub fn learning_path(&self, target: Id) -> Result<Vec<Id>, BuissonError<T>> {
+        let mut path = HashSet::new();
+        let mut stack = vec![target];
+        while let Some(id) = stack.pop() {
+            let node = self.get(id)?;
+            if node.status != NodeStatus::Ok && path.insert(id) {
+                stack.extend(&node.lesson.direct_prerequisites);
+            }
+        }
+
+        let mut ids = self.get_ids();
+        ids.sort_by(|a, b| self.nodes[a].lesson.name.cmp(&self.nodes[b].lesson.name));
+        Ok(self
+            .topological_order(&ids)
+            .into_iter()
+            .filter(|id| path.contains(id))
+            .collect())
+    }
+}
+
+/// How many days it takes to study `num_lessons` lessons at `lessons_per_day` lessons a day.
+pub fn days_to_study(num_lessons: usize, lessons_per_day: u32) -> usize {
+    num_lessons.div_ceil(std::cmp::max(lessons_per_day, 1) as usize)
+}
@@ -0,0 +1,398 @@
+//! A small query language to search for lessons. A query is a list of whitespace separated terms,
+//! all of which must match. Terms are either plain text, fuzzy matched against the name of the
+//! lesson, or filters of the form `field<op>value`:
+//!
+//! - `tag:math` : the lesson has the tag `math`
+//! - `status:pending` : the lesson has the given status. Accepted values are `ok`/`green`,
+//!   `pending`/`orange`, `missing`/`red`, `new`, `known` and `practiced`.
+//! - `level>=3` : the lesson has been practiced to a level of at least 3.
+//! - `due<7d` : the lesson needs to be practiced in less than 7 days. Durations are in days
+//!   (`d`, the default) or weeks (`w`), and can be negative for overdue lessons.
+//! - `requires:"Linear algebra"` : the lesson depends, directly or not, on a lesson whose name
+//!   contains `Linear algebra`.
+//! - `blockers=1` : the lesson has exactly one prerequisite that needs work.
+//! - `lapses>=4` : the lesson was forgotten at least 4 times.
+//! - `priority>=normal` : the lesson has a priority of at least normal. Priorities are `low`,
+//!   `normal` and `high`.
+//!
+//! Numeric fields accept `:`, `=`, `<`, `<=`, `>` and `>=`. Values containing spaces can be
+//! quoted, and any term can be negated with a leading `-`, as in `-tag:math`.
+
+use std::collections::{HashMap, HashSet};
+
+use chrono::NaiveDate;
+
+use crate::{
+    fuzzy::{self, FuzzyMatch},
+    GraphNode, Id, LessonStatus, NodeStatus, Priority,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Comparison {
+    Equal,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+}
+
+impl Comparison {
+    fn compare<T: PartialOrd>(&self, left: T, right: T) -> bool {
+        match self {
+            Comparison::Equal => left == right,
+            Comparison::Less => left < right,
+            Comparison::LessOrEqual => left <= right,
+            Comparison::Greater => left > right,
+            Comparison::GreaterOrEqual => left >= right,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum StatusFilter {
+    Ok,
+    Pending,
+    MissingPrereq,
+    NotPracticed,
+    GoodEnough,
+    Practiced,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Filter {
+    Tag(String),
+    Status(StatusFilter),
+    Level(Comparison, u32),
+    Due(Comparison, i64),
+    Requires(String),
+    Blockers(Comparison, usize),
+    Lapses(Comparison, u32),
+    Priority(Comparison, Priority),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Term {
+    negated: bool,
+    filter: Filter,
+}
+
+/// A parsed search query.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Query {
+    /// the plain text part of the query, matched against lesson names
+    name: String,
+    terms: Vec<Term>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum QueryParseError {
+    /// A quote was opened but never closed.
+    UnterminatedQuote,
+    /// The value given to a filter does not make sense for it, like `level>=high`.
+    InvalidValue { field: String, value: String },
+    /// The filter only accepts `:`, like `tag<math`.
+    InvalidOperator { field: String },
+}
+
+impl std::fmt::Display for QueryParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            QueryParseError::UnterminatedQuote => write!(f, "unterminated quote"),
+            QueryParseError::InvalidValue { field, value } => {
+                write!(f, "invalid value \"{value}\" for \"{field}\"")
+            }
+            QueryParseError::InvalidOperator { field } => {
+                write!(f, "\"{field}\" can only be used with ':'")
+            }
+        }
+    }
+}
+
+/// Split `query` on whitespace, except inside double quotes. Quotes are removed.
+fn tokenize(query: &str) -> Result<Vec<String>, QueryParseError> {
+    let mut tokens = vec![];
+    let mut current = String::new();
+    let mut in_quotes = false;
+
+    for c in query.chars() {
+        match c {
+            '"' => in_quotes = !in_quotes,
+            c if c.is_whitespace() && !in_quotes => {
+                if !current.is_empty() {
+                    tokens.push(std::mem::take(&mut current));
+                }
+            }
+            c => current.push(c),
+        }
+    }
+
+    if in_quotes {
+        return Err(QueryParseError::UnterminatedQuote);
+    }
+    if !current.is_empty() {
+        tokens.push(current);
+    }
+    Ok(tokens)
+}
+
+/// Split a token like `level>=3` into its field, operator and value. Return `None` if the token
+/// isn't of this form.
+fn split_filter(token: &str) -> Option<(&str, Comparison, &str)> {
+    let field_end = token.find(|c: char| !c.is_ascii_alphabetic())?;
+    let (field, rest) = token.split_at(field_end);
+    if field.is_empty() {
+        return None;
+    }
+
+    let operators = [
+        ("<=", Comparison::LessOrEqual),
+        (">=", Comparison::GreaterOrEqual),
+        ("<", Comparison::Less),
+        (">", Comparison::Greater),
+        ("=", Comparison::Equal),
+        (":", Comparison::Equal),
+    ];
+    operators.iter().find_map(|(symbol, comparison)| {
+        rest.strip_prefix(symbol)
+            .map(|value| (field, *comparison, value))
+    })
+}
+
+/// Parse a duration in days, like `7d`, `2w` or `-3`.
+fn parse_days(value: &str) -> Option<i64> {
+    if let Some(weeks) = value.strip_suffix('w') {
+        weeks.parse::<i64>().ok().map(|weeks| 7 * weeks)
+    } else {
+        value.strip_suffix('d').unwrap_or(value).parse().ok()
+    }
+}
+
+impl Filter {
+    /// Parse the filter in `token`. Return `Ok(None)` if `token` isn't a filter, in which case it
+    /// is plain text.
+    fn parse(token: &str) -> Result<Option<Self>, QueryParseError> {
+        let Some((field, comparison, value)) = split_filter(token) else {
+            return Ok(None);
+        };
+
+        let invalid_value = || QueryParseError::InvalidValue {
+            field: field.to_string(),
+            value: value.to_string(),
+        };
+        let only_equal = || {
+            if comparison == Comparison::Equal {
+                Ok(())
+            } else {
+                Err(QueryParseError::InvalidOperator {
+                    field: field.to_string(),
+                })
+            }
+        };
+
+        let filter = match field.to_lowercase().as_str() {
+            "tag" => {
+                only_equal()?;
+                Filter::Tag(fuzzy::normalize(value))
+            }
+            "status" => {
+                only_equal()?;
+                let status = match value.to_lowercase().as_str() {
+                    "ok" | "green" => StatusFilter::Ok,
+                    "pending" | "orange" | "yellow" => StatusFilter::Pending,
+                    "missing" | "blocked" | "red" => StatusFilter::MissingPrereq,
+                    "new" | "never" => StatusFilter::NotPracticed,
+                    "known" => StatusFilter::GoodEnough,
+                    "practiced" => StatusFilter::Practiced,
+                    _ => return Err(invalid_value()),
+                };
+                Filter::Status(status)
+            }
+            "level" => Filter::Level(comparison, value.parse().map_err(|_| invalid_value())?),
+            "due" => Filter::Due(comparison, parse_days(value).ok_or_else(invalid_value)?),
+            "requires" => {
+                only_equal()?;
+                Filter::Requires(fuzzy::normalize(value))
+            }
+            "blockers" => Filter::Blockers(comparison, value.parse().map_err(|_| invalid_value())?),
+            "priority" => {
+                let priority = match value.to_lowercase().as_str() {
+                    "low" => Priority::Low,
+                    "normal" => Priority::Normal,
+                    "high" => Priority::High,
+                    _ => return Err(invalid_value()),
+                };
+                Filter::Priority(comparison, priority)
+            }
+            "lapses" => Filter::Lapses(comparison, value.parse().map_err(|_| invalid_value())?),
+            _ => return Ok(None),
+        };
+        Ok(Some(filter))
+    }
+
+    fn matches(&self, id: Id, lessons: &HashMap<Id, GraphNode>, today: NaiveDate) -> bool {
+        let node = &lessons[&id];
+        match self {
+            Filter::Tag(tag) => node
+                .lesson
+                .tags
+                .iter()
+                .any(|lesson_tag| fuzzy::normalize(lesson_tag) == *tag),
+            Filter::Status(status) => match status {
+                StatusFilter::Ok => node.status == NodeStatus::Ok,
+                StatusFilter::Pending => node.status == NodeStatus::Pending,
+                StatusFilter::MissingPrereq => {
+                    matches!(node.status, NodeStatus::MissingPrereq(_))
+                }
+                StatusFilter::NotPracticed => {
+                    matches!(node.lesson.status, LessonStatus::NotPracticed)
+                }
+                StatusFilter::GoodEnough => matches!(node.lesson.status, LessonStatus::GoodEnough),
+                StatusFilter::Practiced => {
+                    matches!(node.lesson.status, LessonStatus::Practiced { .. })
+                }
+            },
+            Filter::Level(comparison, value) => match node.lesson.status {
+                LessonStatus::Practiced { level, .. } => comparison.compare(level, *value),
+                _ => false,
+            },
+            Filter::Due(comparison, days) => match node.lesson.status {
+                LessonStatus::Practiced { good_until, .. } => {
+                    comparison.compare((good_until - today).num_days(), *days)
+                }
+                _ => false,
+            },
+            Filter::Requires(name) => {
+                // depth first search of the ancestors of `id`
+                let mut visited = HashSet::new();
+                let mut stack = node.lesson.direct_prerequisites.clone();
+                while let Some(prereq_id) = stack.pop() {
+                    if !visited.insert(prereq_id) {
+                        continue;
+                    }
+                    let prereq = &lessons[&prereq_id];
+                    if fuzzy::normalize(&prereq.lesson.name).contains(name.as_str()) {
+                        return true;
+                    }
+                    stack.extend(&prereq.lesson.direct_prerequisites);
+                }
+                false
+            }
+            Filter::Blockers(comparison, value) => {
+                let blockers = match &node.status {
+                    NodeStatus::MissingPrereq(missing) => missing.len(),
+                    NodeStatus::Ok | NodeStatus::Pending => 0,
+                };
+                comparison.compare(blockers, *value)
+            }
+            Filter::Lapses(comparison, value) => comparison.compare(node.lesson.lapses, *value),
+            Filter::Priority(comparison, priority) => {
+                comparison.compare(node.lesson.priority, *priority)
+            }
+        }
+    }
+}
+
+impl std::str::FromStr for Query {
+    type Err = QueryParseError;
+
+    fn from_str(query: &str) -> Result<Self, Self::Err> {
+        let mut name_words = vec![];
+        let mut terms = vec![];
+
+        for token in tokenize(query)? {
+            let (negated, rest) = match token.strip_prefix('-') {
+                Some(rest) => (true, rest),
+                None => (false, token.as_str()),
+            };
+            match Filter::parse(rest)? {
+                Some(filter) => terms.push(Term { negated, filter }),
+                None => name_words.push(token),
+            }
+        }
+
+        Ok(Self {
+            name: name_words.join(" "),
+            terms,
+        })
+    }
+}
+
+impl Query {
+    /// Return whether the lesson `id` passes every filter of the query, ignoring its name.
+    fn matches_filters(&self, id: Id, lessons: &HashMap<Id, GraphNode>, today: NaiveDate) -> bool {
+        self.terms
+            .iter()
+            .all(|term| term.filter.matches(id, lessons, today) != term.negated)
+    }
+
+    /// Return the lessons among `candidates` matching the query, best name matches first, along
+    /// with how their name matched. `lessons` must contain every lesson, not only the
+    /// candidates, as some filters look at prerequisites.
+    pub fn search(
+        &self,
+        lessons: &HashMap<Id, GraphNode>,
+        candidates: impl Iterator<Item = Id>,
+    ) -> Vec<(Id, FuzzyMatch)> {
+        let today = chrono::offset::Local::now().date_naive();
+        fuzzy::rank(
+            &self.name,
+            candidates
+                .filter(|&id| self.matches_filters(id, lessons, today))
+                .map(|id| (id, lessons[&id].lesson.name.as_str())),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parsing() {
+        let query: Query = r#"tag:math status:pending level>=3 due<1w requires:"Linear algebra" -blockers=1 inverse"#
+            .parse()
+            .unwrap();
+
+        assert_eq!(query.name, "inverse");
+        assert_eq!(
+            query.terms.iter().map(|t| &t.filter).collect::<Vec<_>>(),
+            vec![
+                &Filter::Tag(String::from("math")),
+                &Filter::Status(StatusFilter::Pending),
+                &Filter::Level(Comparison::GreaterOrEqual, 3),
+                &Filter::Due(Comparison::Less, 7),
+                &Filter::Requires(String::from("linear algebra")),
+                &Filter::Blockers(Comparison::Equal, 1),
+            ]
+        );
+        assert!(query.terms[5].negated);
+    }
+
+    #[test]
+    fn test_plain_text() {
+        let query: Query = "Chapter 1: intro".parse().unwrap();
+        assert_eq!(query.name, "Chapter 1: intro");
+        assert!(query.terms.is_empty());
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(
+            "level>=high".parse::<Query>(),
+            Err(QueryParseError::InvalidValue {
+                field: String::from("level"),
+                value: String::from("high"),
+            })
+        );
+        assert_eq!(
+            "requires:\"Linear".parse::<Query>(),
+            Err(QueryParseError::UnterminatedQuote)
+        );
+        assert_eq!(
+            "tag<math".parse::<Query>(),
+            Err(QueryParseError::InvalidOperator {
+                field: String::from("tag")
+            })
+        );
+    }
+}
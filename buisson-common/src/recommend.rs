@@ -0,0 +1,97 @@
+//! Choosing which pending lesson to study next.
+
+use std::collections::HashSet;
+
+use chrono::NaiveDate;
+
+use crate::{Graph, IOBackend, Id, NodeStatus, Priority};
+
+impl Priority {
+    /// How much the priority of a lesson multiplies its recommendation score.
+    fn weight(&self) -> f64 {
+        match self {
+            Priority::Low => 0.5,
+            Priority::Normal => 1.0,
+            Priority::High => 2.0,
+        }
+    }
+}
+
+/// Why a lesson is recommended.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Recommendation {
+    pub id: Id,
+    /// how many lessons currently missing a prerequisite would no longer be, if this lesson was
+    /// studied.
+    pub unlocks: usize,
+    /// how late the lesson is relative to its interval, 0 if it is not late or never practiced.
+    pub overdueness: f64,
+    pub score: f64,
+}
+
+impl<T: IOBackend> Graph<T> {
+    /// Rank the pending lessons by how useful it is to study them, best first. A lesson scores
+    /// `priority * (1 + unlocks + overdueness)`: the lessons that unblock the most others come
+    /// first, then the ones that are the most overdue, and the priority set by the user scales it
+    /// all.
+    pub fn recommendations(&self, today: NaiveDate) -> Vec<Recommendation> {
+        let order = self.topological_order(&self.get_ids());
+
+        let mut recommendations = self
+            .nodes
+            .iter()
+            .filter(|(_, node)| node.status == NodeStatus::Pending)
+            .map(|(&id, node)| {
+                let unlocks = self.num_unlocked_by(id, &order);
+                let overdueness = node
+                    .lesson
+                    .status
+                    .overdueness(today)
+                    .unwrap_or_default()
+                    .max(0.0);
+                let score = node.lesson.priority.weight() * (1.0 + unlocks as f64 + overdueness);
+                Recommendation {
+                    id,
+                    unlocks,
+                    overdueness,
+                    score,
+                }
+            })
+            .collect::<Vec<_>>();
+
+        recommendations.sort_by(|a, b| b.score.total_cmp(&a.score).then(a.id.cmp(&b.id)));
+        recommendations
+    }
+
+    /// Return the pending lesson that is the most useful to study now, if there is one. See
+    /// `recommendations`.
+    pub fn recommend_next(&self, today: NaiveDate) -> Option<Id> {
+        self.recommendations(today).first().map(|r| r.id)
+    }
+
+    /// Count the lessons missing a prerequisite that would no longer be if the pending lesson
+    /// `id` was studied. `order` contains every lesson, prerequisites first.
+    fn num_unlocked_by(&self, id: Id, order: &[Id]) -> usize {
+        let descendants = self.descendants(id);
+        // the lessons that would be `Ok` once `id` is studied, on top of the ones that already are
+        let mut ok_after = HashSet::from([id]);
+        let mut unlocked = 0;
+
+        for child in order.iter().filter(|child| descendants.contains(child)) {
+            let node = &self.nodes[child];
+            if !matches!(node.status, NodeStatus::MissingPrereq(_)) {
+                continue;
+            }
+            let prereqs_ok = node.lesson.direct_prerequisites.iter().all(|prereq| {
+                ok_after.contains(prereq) || self.nodes[prereq].status == NodeStatus::Ok
+            });
+            if prereqs_ok {
+                unlocked += 1;
+                if !node.lesson.status.needs_work() {
+                    ok_after.insert(*child);
+                }
+            }
+        }
+        unlocked
+    }
+}
@@ -0,0 +1,100 @@
+//! Finding the prerequisites that a lesson already requires through its other prerequisites.
+//! Listing them is noise: if A requires B and B requires C, A requires C too.
+
+use std::collections::{HashMap, HashSet};
+
+use crate::{BuissonError, Graph, GraphNode, IOBackend, Id};
+
+/// A prerequisite of a lesson that another of its prerequisites already depends on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RedundantPrerequisite {
+    pub prerequisite: Id,
+    /// the other prerequisite of the lesson, that depends on `prerequisite`
+    pub through: Id,
+}
+
+/// Return every lesson that `id` depends on, directly or not.
+fn ancestors(lessons: &HashMap<Id, GraphNode>, id: Id) -> HashSet<Id> {
+    let mut ancestors = HashSet::new();
+    let mut stack = vec![id];
+    while let Some(id) = stack.pop() {
+        for &prerequisite in &lessons[&id].lesson.direct_prerequisites {
+            if ancestors.insert(prerequisite) {
+                stack.push(prerequisite);
+            }
+        }
+    }
+    ancestors
+}
+
+/// Return the lessons of `prerequisites` that another one of them already depends on. This works
+/// on prerequisites that are not saved yet, like the ones of a lesson being edited.
+pub fn redundant_among(
+    lessons: &HashMap<Id, GraphNode>,
+    prerequisites: &[Id],
+) -> Vec<RedundantPrerequisite> {
+    let ancestors = prerequisites
+        .iter()
+        .map(|&id| (id, ancestors(lessons, id)))
+        .collect::<Vec<_>>();
+    prerequisites
+        .iter()
+        .filter_map(|&prerequisite| {
+            ancestors
+                .iter()
+                .find(|(_, ancestors)| ancestors.contains(&prerequisite))
+                .map(|&(through, _)| RedundantPrerequisite {
+                    prerequisite,
+                    through,
+                })
+        })
+        .collect()
+}
+
+impl<T: IOBackend> Graph<T> {
+    /// Return the direct prerequisites of `id` that another of its direct prerequisites already
+    /// depends on.
+    pub fn redundant_prerequisites(
+        &self,
+        id: Id,
+    ) -> Result<Vec<RedundantPrerequisite>, BuissonError<T>> {
+        Ok(redundant_among(
+            &self.nodes,
+            &self.get(id)?.lesson.direct_prerequisites,
+        ))
+    }
+
+    /// Return the redundant prerequisites of every lesson that has some, sorted by id.
+    pub fn all_redundant_prerequisites(&self) -> Vec<(Id, Vec<RedundantPrerequisite>)> {
+        let mut ids = self.get_ids();
+        ids.sort();
+        ids.into_iter()
+            .filter_map(|id| {
+                let redundant =
+                    redundant_among(&self.nodes, &self.nodes[&id].lesson.direct_prerequisites);
+                (!redundant.is_empty()).then_some((id, redundant))
+            })
+            .collect()
+    }
+
+    /// Remove every redundant prerequisite, so that lessons only list the prerequisites they
+    /// don't already require through others. This is the transitive reduction of the graph: what
+    /// each lesson requires, directly or not, does not change. Return what was removed.
+    pub fn remove_redundant_prerequisites(
+        &mut self,
+    ) -> Result<Vec<(Id, Vec<RedundantPrerequisite>)>, BuissonError<T>> {
+        // removing a redundant prerequisite does not change what lessons require, so the others
+        // stay redundant
+        let all_redundant = self.all_redundant_prerequisites();
+        for (id, redundant) in &all_redundant {
+            let mut lesson = self.get(*id)?.lesson.clone();
+            lesson.direct_prerequisites.retain(|prerequisite| {
+                !redundant
+                    .iter()
+                    .any(|redundant| redundant.prerequisite == *prerequisite)
+            });
+            self.edit_node(*id, lesson)?;
+        }
+        Ok(all_redundant)
+    }
+}
@@ -0,0 +1,195 @@
+//! Merging and splitting lessons, as the understanding of a subject evolves: two lessons can turn
+//! out to be the same concept, and one can turn out to be too big.
+
+use chrono::NaiveDate;
+
+use crate::{BuissonError, Graph, IOBackend, Id, LessonInfo, LessonStatus, Uid};
+
+/// A part of a lesson being split, along with the prerequisites and dependents of the lesson it
+/// takes.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SplitPart {
+    pub name: String,
+    pub prerequisites: Vec<Id>,
+    pub dependents: Vec<Id>,
+}
+
+/// The status of `a` and `b` that needs the most work: a lesson never studied needs more than a
+/// practiced one, which needs more than a known one. Between practiced lessons, the one at the
+/// lowest step does, and then the one due first.
+fn most_conservative(a: LessonStatus, b: LessonStatus) -> LessonStatus {
+    fn rank(status: &LessonStatus) -> (u8, u32, Option<NaiveDate>) {
+        match *status {
+            LessonStatus::NotPracticed => (0, 0, None),
+            LessonStatus::Practiced {
+                level, good_until, ..
+            } => (1, level, Some(good_until)),
+            LessonStatus::GoodEnough => (2, 0, None),
+        }
+    }
+
+    if rank(&b) < rank(&a) {
+        b
+    } else {
+        a
+    }
+}
+
+/// Replace `old` with the ids of `new` in `prerequisites`, without duplicates.
+fn replace_prerequisite(prerequisites: &[Id], old: Id, new: &[Id]) -> Vec<Id> {
+    let mut replaced = vec![];
+    for prerequisite in prerequisites {
+        let ids = if *prerequisite == old {
+            new
+        } else {
+            std::slice::from_ref(prerequisite)
+        };
+        for &id in ids {
+            if !replaced.contains(&id) {
+                replaced.push(id);
+            }
+        }
+    }
+    replaced
+}
+
+impl<T: IOBackend> Graph<T> {
+    /// Return whether lessons `id` and `other` can be merged. They can't if one depends on the
+    /// other through a third lesson, as the merged lesson would then depend on itself.
+    pub fn can_merge(&self, id: Id, other: Id) -> Result<bool, BuissonError<T>> {
+        if id == other {
+            return Ok(false);
+        }
+        for (a, b) in [(id, other), (other, id)] {
+            for &prerequisite in &self.get(a)?.lesson.direct_prerequisites {
+                if prerequisite != b && self.depends_on(prerequisite, b)? {
+                    return Ok(false);
+                }
+            }
+        }
+        Ok(true)
+    }
+
+    /// Merge lesson `other` into lesson `id`, and delete `other`. The merged lesson keeps the name
+    /// of `id`, and gets the prerequisites, dependents, tags and lapses of both, the status that
+    /// needs the most work, the highest priority and the earliest deadline. The reviews of `other`
+    /// are not moved: they stay recorded as the ones of a deleted lesson.
+    pub fn merge_nodes(&mut self, id: Id, other: Id) -> Result<(), BuissonError<T>> {
+        if !self.can_merge(id, other)? {
+            return Err(BuissonError::WouldCreateCycle(id, other));
+        }
+        let lesson = self.get(id)?.lesson.clone();
+        let other_lesson = self.get(other)?.lesson.clone();
+
+        for child in self.get_children(other)?.to_vec() {
+            if child != id {
+                let mut child_lesson = self.get(child)?.lesson.clone();
+                child_lesson.direct_prerequisites =
+                    replace_prerequisite(&child_lesson.direct_prerequisites, other, &[id]);
+                self.edit_node(child, child_lesson)?;
+            }
+        }
+
+        let mut direct_prerequisites: Vec<Id> = vec![];
+        let mut tags: Vec<String> = vec![];
+        for lesson in [&lesson, &other_lesson] {
+            for &prerequisite in &lesson.direct_prerequisites {
+                if prerequisite != id
+                    && prerequisite != other
+                    && !direct_prerequisites.contains(&prerequisite)
+                {
+                    direct_prerequisites.push(prerequisite);
+                }
+            }
+            for tag in &lesson.tags {
+                if !tags.contains(tag) {
+                    tags.push(tag.clone());
+                }
+            }
+        }
+        let merged = LessonInfo {
+            direct_prerequisites,
+            status: most_conservative(lesson.status, other_lesson.status),
+            tags,
+            lapses: lesson.lapses + other_lesson.lapses,
+            priority: std::cmp::max(lesson.priority, other_lesson.priority),
+            deadline: [lesson.deadline, other_lesson.deadline]
+                .into_iter()
+                .flatten()
+                .min(),
+            ..lesson
+        };
+        self.edit_node(id, merged)?;
+
+        self.delete_node(other)
+    }
+
+    /// Split lesson `id` into `parts`, and return the ids of the parts. The first part keeps the
+    /// id of the lesson and its history, and the others are new lessons with the same status,
+    /// tags, priority and deadline.
+    ///
+    /// Each part only gets the prerequisites it is given. Dependents depend on every part they
+    /// are given to, or on the first one if they are given to none. These must all be
+    /// prerequisites and dependents of the lesson.
+    pub fn split_node(
+        &mut self,
+        id: Id,
+        parts: Vec<SplitPart>,
+    ) -> Result<Vec<Id>, BuissonError<T>> {
+        let lesson = self.get(id)?.lesson.clone();
+        let children = self.get_children(id)?.to_vec();
+        for part in &parts {
+            let invalid = part
+                .prerequisites
+                .iter()
+                .find(|prerequisite| !lesson.direct_prerequisites.contains(prerequisite))
+                .or_else(|| {
+                    part.dependents
+                        .iter()
+                        .find(|dependent| !children.contains(dependent))
+                });
+            if let Some(&invalid) = invalid {
+                return Err(BuissonError::InvalidSplit(invalid));
+            }
+        }
+
+        let mut part_ids = vec![];
+        for (index, part) in parts.iter().enumerate() {
+            let part_lesson = LessonInfo {
+                name: part.name.clone(),
+                direct_prerequisites: part.prerequisites.clone(),
+                ..lesson.clone()
+            };
+            if index == 0 {
+                self.edit_node(id, part_lesson)?;
+                part_ids.push(id);
+            } else {
+                part_ids.push(self.create_new_node(LessonInfo {
+                    lapses: 0,
+                    uid: Uid::default(),
+                    ..part_lesson
+                })?);
+            }
+        }
+
+        for child in children {
+            let new_prerequisites = parts
+                .iter()
+                .zip(&part_ids)
+                .filter(|(part, _)| part.dependents.contains(&child))
+                .map(|(_, &part_id)| part_id)
+                .collect::<Vec<_>>();
+            if !new_prerequisites.is_empty() {
+                let mut child_lesson = self.get(child)?.lesson.clone();
+                child_lesson.direct_prerequisites = replace_prerequisite(
+                    &child_lesson.direct_prerequisites,
+                    id,
+                    &new_prerequisites,
+                );
+                self.edit_node(child, child_lesson)?;
+            }
+        }
+
+        Ok(part_ids)
+    }
+}
@@ -0,0 +1,161 @@
+//! The different orders in which lessons can be listed.
+
+use std::{
+    cmp::{Ordering, Reverse},
+    collections::{BTreeSet, HashMap, HashSet},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{fuzzy, Graph, IOBackend, Id, LessonStatus, NodeStatus};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum SortOrder {
+    /// Alphabetical order.
+    #[default]
+    Name,
+    /// Pending lessons first, then the ones missing a prerequisite, then the ones that are ok.
+    Status,
+    /// The lessons to practice the soonest first. Lessons without a due date come last.
+    DueDate,
+    /// The least practiced lessons first. Never practiced lessons come first, and lessons that
+    /// are good enough last.
+    Level,
+    /// The lessons that are the most overdue relative to their interval first, so that a lesson
+    /// 3 days late on a 2 day interval comes before one 5 days late on a 60 day interval.
+    MostOverdue,
+    /// The lessons with the most lessons depending on them, directly or not, first.
+    Dependents,
+    /// Prerequisites before the lessons depending on them.
+    Topological,
+}
+
+impl SortOrder {
+    pub const ALL: [SortOrder; 7] = [
+        SortOrder::Name,
+        SortOrder::Status,
+        SortOrder::DueDate,
+        SortOrder::Level,
+        SortOrder::MostOverdue,
+        SortOrder::Dependents,
+        SortOrder::Topological,
+    ];
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            SortOrder::Name => "name",
+            SortOrder::Status => "status",
+            SortOrder::DueDate => "due date",
+            SortOrder::Level => "level",
+            SortOrder::MostOverdue => "most overdue",
+            SortOrder::Dependents => "number of dependents",
+            SortOrder::Topological => "prerequisites first",
+        }
+    }
+}
+
+impl<T: IOBackend> Graph<T> {
+    /// Return the ids of every lesson, sorted by `order`. Lessons that are equal for `order` are
+    /// sorted by name.
+    pub fn sorted_ids(&self, order: SortOrder) -> Vec<Id> {
+        let names = self
+            .nodes
+            .iter()
+            .map(|(&id, node)| (id, fuzzy::normalize(&node.lesson.name)))
+            .collect::<HashMap<_, _>>();
+        let by_name = |a: &Id, b: &Id| names[a].cmp(&names[b]).then(a.cmp(b));
+
+        let mut ids = self.get_ids();
+        ids.sort_by(by_name);
+
+        if order == SortOrder::Topological {
+            return self.topological_order(&ids);
+        }
+
+        let today = chrono::offset::Local::now().date_naive();
+        let status = |id: &Id| &self.nodes[id].lesson.status;
+
+        match order {
+            SortOrder::Name | SortOrder::Topological => (),
+            SortOrder::Status => ids.sort_by_key(|id| match self.nodes[id].status {
+                NodeStatus::Pending => 0,
+                NodeStatus::MissingPrereq(_) => 1,
+                NodeStatus::Ok => 2,
+            }),
+            SortOrder::DueDate => ids.sort_by_key(|id| match status(id) {
+                LessonStatus::Practiced { good_until, .. } => (0, Some(*good_until)),
+                LessonStatus::NotPracticed | LessonStatus::GoodEnough => (1, None),
+            }),
+            SortOrder::Level => ids.sort_by_key(|id| match status(id) {
+                LessonStatus::NotPracticed => (0, 0),
+                LessonStatus::Practiced { level, .. } => (1, *level),
+                LessonStatus::GoodEnough => (2, 0),
+            }),
+            SortOrder::MostOverdue => {
+                let overdueness = |id: &Id| status(id).overdueness(today);
+                // lessons without a due date come last
+                ids.sort_by(|a, b| match (overdueness(a), overdueness(b)) {
+                    (Some(a), Some(b)) => b.total_cmp(&a),
+                    (Some(_), None) => Ordering::Less,
+                    (None, Some(_)) => Ordering::Greater,
+                    (None, None) => Ordering::Equal,
+                })
+            }
+            SortOrder::Dependents => {
+                let counts = ids
+                    .iter()
+                    .map(|&id| (id, self.descendants(id).len()))
+                    .collect::<HashMap<_, _>>();
+                ids.sort_by_key(|id| Reverse(counts[id]));
+            }
+        }
+        ids
+    }
+
+    /// Return the ids of every lesson that depends on `id`, directly or not.
+    pub fn descendants(&self, id: Id) -> HashSet<Id> {
+        let mut descendants = HashSet::new();
+        let mut stack = self.children[&id].clone();
+        while let Some(child) = stack.pop() {
+            if descendants.insert(child) {
+                stack.extend(&self.children[&child]);
+            }
+        }
+        descendants
+    }
+
+    /// Sort `ids`, which must contain every lesson, so that every lesson comes after its
+    /// prerequisites. Among the lessons that could come next, the one that comes first in `ids`
+    /// is picked.
+    pub(crate) fn topological_order(&self, ids: &[Id]) -> Vec<Id> {
+        let rank = ids
+            .iter()
+            .enumerate()
+            .map(|(rank, &id)| (id, rank))
+            .collect::<HashMap<_, _>>();
+        let mut missing_prereqs = ids
+            .iter()
+            .map(|id| (*id, self.nodes[id].lesson.direct_prerequisites.len()))
+            .collect::<HashMap<_, _>>();
+
+        // the lessons whose prerequisites have all been placed, by rank
+        let mut ready = ids
+            .iter()
+            .filter(|id| missing_prereqs[id] == 0)
+            .map(|id| (rank[id], *id))
+            .collect::<BTreeSet<_>>();
+
+        let mut order = Vec::with_capacity(ids.len());
+        while let Some((_, id)) = ready.pop_first() {
+            order.push(id);
+            for child in &self.children[&id] {
+                let count = missing_prereqs.get_mut(child).unwrap();
+                *count -= 1;
+                if *count == 0 {
+                    ready.insert((rank[child], *child));
+                }
+            }
+        }
+        order
+    }
+}
@@ -0,0 +1,199 @@
+//! Statistics about the lessons and the review history, for the statistics screen.
+
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    time::Duration,
+};
+
+use chrono::{Days, NaiveDate};
+
+use crate::{Graph, IOBackend, Id, LessonStatus, NodeStatus};
+
+/// How many days of review history are kept in `Statistics::reviews_per_day`.
+const REVIEW_HISTORY_DAYS: u64 = 30;
+/// How many lessons are kept in the rankings of `Statistics`.
+const RANKING_LENGTH: usize = 10;
+
+#[derive(Debug, Default, PartialEq)]
+pub struct Statistics {
+    /// how many practiced lessons there are at each level.
+    pub levels: BTreeMap<u32, usize>,
+    pub not_practiced: usize,
+    pub good_enough: usize,
+    pub practiced: usize,
+    pub ok: usize,
+    pub pending: usize,
+    pub missing_prereq: usize,
+    /// how many reviews were made on each of the last days, oldest first and ending today.
+    pub reviews_per_day: Vec<(NaiveDate, usize)>,
+    /// how many days in a row, up to today, at least one review was made. Today does not break
+    /// the streak if nothing was reviewed yet.
+    pub current_streak: u32,
+    pub longest_streak: u32,
+    /// the practiced lessons that are due, with how many days late they are, latest first.
+    pub most_overdue: Vec<(Id, i64)>,
+    /// the lessons that need work, with how many lessons missing a prerequisite depend on them,
+    /// directly or not, most blocking first.
+    pub most_blocking: Vec<(Id, usize)>,
+    /// the lessons studied the longest in total, longest first. Untimed reviews are not counted.
+    pub time_per_lesson: Vec<(Id, Duration)>,
+    /// how long the lessons with each tag were studied in total, longest first.
+    pub time_per_tag: Vec<(String, Duration)>,
+    /// the average length of a timed review, if there is one.
+    pub average_review_time: Option<Duration>,
+    /// how long studying every pending lesson should take. Each lesson is expected to take its
+    /// average review time, or the average of all lessons if it was never timed.
+    pub todays_queue_time: Option<Duration>,
+}
+
+impl<T: IOBackend> Graph<T> {
+    /// Compute the statistics of the lessons and of the review history, as of `today`.
+    pub fn statistics(&self, today: NaiveDate) -> Statistics {
+        let mut stats = Statistics::default();
+
+        for node in self.nodes.values() {
+            match node.lesson.status {
+                LessonStatus::NotPracticed => stats.not_practiced += 1,
+                LessonStatus::GoodEnough => stats.good_enough += 1,
+                LessonStatus::Practiced { level, .. } => {
+                    stats.practiced += 1;
+                    *stats.levels.entry(level).or_default() += 1;
+                }
+            }
+            match node.status {
+                NodeStatus::Ok => stats.ok += 1,
+                NodeStatus::Pending => stats.pending += 1,
+                NodeStatus::MissingPrereq(_) => stats.missing_prereq += 1,
+            }
+        }
+
+        let review_days = self
+            .reviews
+            .iter()
+            .map(|review| review.date)
+            .collect::<HashSet<_>>();
+
+        stats.reviews_per_day = (0..REVIEW_HISTORY_DAYS)
+            .rev()
+            .map(|days_ago| {
+                let date = today - Days::new(days_ago);
+                let count = self.reviews.iter().filter(|r| r.date == date).count();
+                (date, count)
+            })
+            .collect();
+
+        let yesterday = today - Days::new(1);
+        let mut day = if review_days.contains(&today) {
+            today
+        } else {
+            yesterday
+        };
+        while review_days.contains(&day) {
+            stats.current_streak += 1;
+            day = day - Days::new(1);
+        }
+
+        let mut sorted_days = review_days.into_iter().collect::<Vec<_>>();
+        sorted_days.sort();
+        let mut streak = 0;
+        for (i, day) in sorted_days.iter().enumerate() {
+            let follows_previous = i > 0 && sorted_days[i - 1] + Days::new(1) == *day;
+            streak = if follows_previous { streak + 1 } else { 1 };
+            stats.longest_streak = std::cmp::max(stats.longest_streak, streak);
+        }
+
+        stats.most_overdue = self
+            .nodes
+            .iter()
+            .filter_map(|(&id, node)| match node.lesson.status {
+                LessonStatus::Practiced { good_until, .. } if good_until <= today => {
+                    Some((id, (today - good_until).num_days()))
+                }
+                _ => None,
+            })
+            .collect();
+        stats
+            .most_overdue
+            .sort_by_key(|&(id, days)| (std::cmp::Reverse(days), id));
+        stats.most_overdue.truncate(RANKING_LENGTH);
+
+        stats.most_blocking = self
+            .nodes
+            .iter()
+            .filter(|(_, node)| node.status != NodeStatus::Ok)
+            .map(|(&id, _)| {
+                let blocked = self
+                    .descendants(id)
+                    .iter()
+                    .filter(|child| {
+                        matches!(self.nodes[child].status, NodeStatus::MissingPrereq(_))
+                    })
+                    .count();
+                (id, blocked)
+            })
+            .filter(|&(_, blocked)| blocked > 0)
+            .collect();
+        stats
+            .most_blocking
+            .sort_by_key(|&(id, blocked)| (std::cmp::Reverse(blocked), id));
+        stats.most_blocking.truncate(RANKING_LENGTH);
+
+        self.add_time_statistics(&mut stats);
+        stats
+    }
+
+    /// Fill the statistics of `stats` about the time spent studying.
+    fn add_time_statistics(&self, stats: &mut Statistics) {
+        // the total time spent on each lesson, and the number of timed reviews it adds up
+        let mut time_per_lesson: HashMap<Id, (Duration, u32)> = HashMap::new();
+        for review in &self.reviews {
+            if let Some(duration) = review.duration {
+                let (total, count) = time_per_lesson.entry(review.lesson).or_default();
+                *total += duration;
+                *count += 1;
+            }
+        }
+
+        let (total, count) = time_per_lesson
+            .values()
+            .fold((Duration::ZERO, 0), |(total, count), (time, num)| {
+                (total + *time, count + num)
+            });
+        stats.average_review_time = (count > 0).then(|| total / count);
+
+        stats.todays_queue_time = stats.average_review_time.map(|average| {
+            self.nodes
+                .iter()
+                .filter(|(_, node)| node.status == NodeStatus::Pending)
+                .map(|(id, _)| match time_per_lesson.get(id) {
+                    Some((time, num)) => *time / *num,
+                    None => average,
+                })
+                .sum()
+        });
+
+        // lessons deleted since they were reviewed are left out
+        let mut time_per_tag: HashMap<&str, Duration> = HashMap::new();
+        for (id, (time, _)) in &time_per_lesson {
+            if let Some(node) = self.nodes.get(id) {
+                stats.time_per_lesson.push((*id, *time));
+                for tag in &node.lesson.tags {
+                    *time_per_tag.entry(tag).or_default() += *time;
+                }
+            }
+        }
+        stats
+            .time_per_lesson
+            .sort_by_key(|&(id, time)| (std::cmp::Reverse(time), id));
+        stats.time_per_lesson.truncate(RANKING_LENGTH);
+
+        stats.time_per_tag = time_per_tag
+            .into_iter()
+            .map(|(tag, time)| (String::from(tag), time))
+            .collect();
+        stats
+            .time_per_tag
+            .sort_by(|(tag_a, time_a), (tag_b, time_b)| time_b.cmp(time_a).then(tag_a.cmp(tag_b)));
+        stats.time_per_tag.truncate(RANKING_LENGTH);
+    }
+}
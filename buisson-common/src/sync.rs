@@ -0,0 +1,371 @@
+//! Merging two copies of the same lessons that were edited separately, like the databases of two
+//! machines.
+
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Display,
+    time::Duration,
+};
+
+use chrono::{NaiveDate, NaiveDateTime};
+
+use crate::{grade::Grade, Id, LessonInfo, LessonStatus, Review, Uid};
+
+/// A lesson, along with when it was last modified.
+#[derive(Debug, Clone)]
+pub struct LessonRecord {
+    pub lesson: LessonInfo,
+    /// `None` if the lesson was last modified before modifications were tracked.
+    pub modified: Option<NaiveDateTime>,
+}
+
+/// Everything that is merged from one copy of the lessons.
+#[derive(Debug, Default)]
+pub struct SyncData {
+    pub lessons: HashMap<Id, LessonRecord>,
+    /// the lessons that were deleted, with when they were.
+    pub deleted: HashMap<Uid, NaiveDateTime>,
+    pub reviews: Vec<Review>,
+    /// when this copy was last synced, if it ever was.
+    pub last_sync: Option<NaiveDateTime>,
+}
+
+/// A lesson that was changed differently on both sides, which the merge had to choose from.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SyncConflict {
+    /// The lesson was edited on both sides. The latest edit was kept, and `discarded` is the name
+    /// the lesson had in the other one.
+    EditedOnBothSides { kept: String, discarded: String },
+    /// The lesson was edited on one side and deleted on the other. It was `kept` if the edit is
+    /// more recent than the deletion.
+    EditedAndDeleted { name: String, kept: bool },
+}
+
+impl Display for SyncConflict {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SyncConflict::EditedOnBothSides { kept, discarded } if kept == discarded => {
+                write!(
+                    f,
+                    "\"{kept}\" was edited on both sides, kept the latest edit"
+                )
+            }
+            SyncConflict::EditedOnBothSides { kept, discarded } => write!(
+                f,
+                "a lesson was edited on both sides, kept \"{kept}\" over \"{discarded}\""
+            ),
+            SyncConflict::EditedAndDeleted { name, kept } => write!(
+                f,
+                "\"{name}\" was edited on one side and deleted on the other, {}",
+                if *kept { "kept it" } else { "deleted it" }
+            ),
+        }
+    }
+}
+
+/// What identifies a review: its uid, or for the reviews recorded before they had one, their
+/// content, as reviews are never modified.
+#[derive(PartialEq, Eq, Hash)]
+enum ReviewKey {
+    Uid(Uid),
+    Content(
+        Id,
+        NaiveDate,
+        Option<Duration>,
+        Option<Grade>,
+        (Option<u32>, Option<NaiveDate>),
+    ),
+}
+
+fn review_key(review: &Review) -> ReviewKey {
+    if !review.uid.is_nil() {
+        return ReviewKey::Uid(review.uid);
+    }
+    let status = match review.status {
+        LessonStatus::Practiced {
+            level, good_until, ..
+        } => (Some(level), Some(good_until)),
+        LessonStatus::NotPracticed | LessonStatus::GoodEnough => (None, None),
+    };
+    ReviewKey::Content(
+        review.lesson,
+        review.date,
+        review.duration,
+        review.grade,
+        status,
+    )
+}
+
+/// The uid of the lesson of each id of `data`.
+fn uids(data: &SyncData) -> HashMap<Id, Uid> {
+    data.lessons
+        .iter()
+        .map(|(&id, record)| (id, record.lesson.uid))
+        .collect()
+}
+
+/// Merge the lessons and reviews of `local` and `remote`, and return the result along with the
+/// conflicts that were found. Lessons are matched by their `Uid`. The latest modification of each
+/// lesson wins, be it an edit or a deletion. Lessons are in conflict when both sides modified
+/// them since they were last synced, and the reviews of both sides are all kept.
+///
+/// Lessons keep their local `Id`. The ones that only exist in `remote` keep theirs too, unless it
+/// is already used locally, in which case they get a new one.
+pub fn merge(local: SyncData, remote: SyncData) -> (SyncData, Vec<SyncConflict>) {
+    // modifications made after this are not known to the other side
+    let last_sync = std::cmp::min(local.last_sync, remote.last_sync);
+    let modified_since_sync = |modified: Option<NaiveDateTime>| match (modified, last_sync) {
+        (Some(modified), Some(last_sync)) => modified > last_sync,
+        (None, _) => false,
+        (Some(_), None) => true,
+    };
+
+    let local_uids = uids(&local);
+    let remote_uids = uids(&remote);
+    let local_ids = local_uids
+        .iter()
+        .map(|(&id, &uid)| (uid, id))
+        .collect::<HashMap<_, _>>();
+    let remote_ids = remote_uids
+        .iter()
+        .map(|(&id, &uid)| (uid, id))
+        .collect::<HashMap<_, _>>();
+
+    let mut merged = SyncData::default();
+    let mut conflicts = vec![];
+    // the lessons to keep, with whether the kept version is the local one
+    let mut kept_records = vec![];
+
+    let all_uids = local_ids
+        .keys()
+        .chain(remote_ids.keys())
+        .chain(local.deleted.keys())
+        .chain(remote.deleted.keys())
+        .copied()
+        .collect::<HashSet<_>>();
+    let mut all_uids = all_uids.into_iter().collect::<Vec<_>>();
+    all_uids.sort();
+
+    for uid in all_uids {
+        let deleted = std::cmp::max(local.deleted.get(&uid), remote.deleted.get(&uid)).copied();
+        let local_record = local_ids.get(&uid).map(|id| (&local.lessons[id], true));
+        let remote_record = remote_ids.get(&uid).map(|id| (&remote.lessons[id], false));
+
+        let kept = match (local_record, remote_record) {
+            (Some(local_record), Some(remote_record)) => {
+                let (newer, older) = if remote_record.0.modified > local_record.0.modified {
+                    (remote_record, local_record)
+                } else {
+                    (local_record, remote_record)
+                };
+                if newer.0.modified != older.0.modified
+                    && modified_since_sync(newer.0.modified)
+                    && modified_since_sync(older.0.modified)
+                {
+                    conflicts.push(SyncConflict::EditedOnBothSides {
+                        kept: newer.0.lesson.name.clone(),
+                        discarded: older.0.lesson.name.clone(),
+                    });
+                }
+                Some(newer)
+            }
+            (Some(record), None) | (None, Some(record)) => match deleted {
+                Some(deleted) => {
+                    let kept = record.0.modified > Some(deleted);
+                    if modified_since_sync(record.0.modified) {
+                        conflicts.push(SyncConflict::EditedAndDeleted {
+                            name: record.0.lesson.name.clone(),
+                            kept,
+                        });
+                    }
+                    kept.then_some(record)
+                }
+                None => Some(record),
+            },
+            (None, None) => None,
+        };
+
+        match (kept, deleted) {
+            (Some((record, is_local)), _) => kept_records.push((uid, record, is_local)),
+            (None, Some(deleted)) => {
+                merged.deleted.insert(uid, deleted);
+            }
+            (None, None) => (),
+        }
+    }
+
+    // the ids that can't be given to lessons only present in `remote`: the ones of local lessons,
+    // and the ones local reviews refer to, whose lessons may have been deleted
+    let mut used_ids = kept_records
+        .iter()
+        .filter_map(|(uid, _, _)| local_ids.get(uid).copied())
+        .chain(local.reviews.iter().map(|review| review.lesson))
+        .collect::<HashSet<_>>();
+    let mut next_free_id = used_ids
+        .iter()
+        .chain(remote_uids.keys())
+        .max()
+        .map_or(0, |max| max + 1);
+
+    let mut new_ids = HashMap::new();
+    for (uid, _, _) in &kept_records {
+        if let Some(&id) = local_ids.get(uid) {
+            new_ids.insert(*uid, id);
+        }
+    }
+    let mut remote_only = kept_records
+        .iter()
+        .filter_map(|(uid, _, _)| match local_ids.contains_key(uid) {
+            true => None,
+            false => Some((remote_ids[uid], *uid)),
+        })
+        .collect::<Vec<_>>();
+    remote_only.sort();
+    for (remote_id, uid) in remote_only {
+        let id = if used_ids.contains(&remote_id) {
+            next_free_id += 1;
+            next_free_id - 1
+        } else {
+            remote_id
+        };
+        used_ids.insert(id);
+        new_ids.insert(uid, id);
+    }
+
+    // prerequisites refer to the ids of the side the lesson comes from. The ones deleted on the
+    // other side are dropped.
+    for (uid, record, is_local) in kept_records {
+        let side_uids = if is_local { &local_uids } else { &remote_uids };
+        let mut record = record.clone();
+        record.lesson.direct_prerequisites = record
+            .lesson
+            .direct_prerequisites
+            .iter()
+            .filter_map(|prereq| new_ids.get(side_uids.get(prereq)?).copied())
+            .collect();
+        merged.lessons.insert(new_ids[&uid], record);
+    }
+
+    // local reviews keep their ids, and remote ones follow their lesson
+    let remote_reviews = remote.reviews.into_iter().filter_map(|mut review| {
+        review.lesson = *new_ids.get(remote_uids.get(&review.lesson)?)?;
+        Some(review)
+    });
+    let mut seen = HashSet::new();
+    merged.reviews = local
+        .reviews
+        .into_iter()
+        .chain(remote_reviews)
+        .filter(|review| seen.insert(review_key(review)))
+        .collect();
+    merged.reviews.sort_by_key(|review| review.date);
+
+    (merged, conflicts)
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::*;
+
+    fn time(hour: u32) -> Option<NaiveDateTime> {
+        NaiveDate::from_ymd_opt(2024, 1, 1)
+            .unwrap()
+            .and_hms_opt(hour, 0, 0)
+    }
+
+    fn record(
+        uid: u128,
+        name: &str,
+        prerequisites: Vec<Id>,
+        modified: Option<NaiveDateTime>,
+    ) -> LessonRecord {
+        LessonRecord {
+            lesson: LessonInfo {
+                name: String::from(name),
+                direct_prerequisites: prerequisites,
+                uid: Uid(uid),
+                ..LessonInfo::default()
+            },
+            modified,
+        }
+    }
+
+    #[test]
+    fn test_merge() {
+        let review = Review {
+            lesson: 0,
+            date: NaiveDate::from_ymd_opt(2024, 1, 1).unwrap(),
+            status: LessonStatus::GoodEnough,
+            grade: None,
+            duration: Some(Duration::from_secs(60)),
+            uid: Uid(10),
+        };
+        // the same lesson was studied twice on the same day, with the same outcome
+        let same_day_review = Review {
+            uid: Uid(11),
+            ..review.clone()
+        };
+        let local = SyncData {
+            lessons: [
+                (0, record(1, "Both", vec![], time(3))),
+                (1, record(2, "Local", vec![0], time(2))),
+                (2, record(3, "Untouched", vec![], time(1))),
+                (3, record(4, "Edited then deleted", vec![], time(4))),
+            ]
+            .into_iter()
+            .collect(),
+            deleted: HashMap::new(),
+            reviews: vec![review.clone(), same_day_review],
+            last_sync: time(1),
+        };
+        // lesson 1 was created separately on both sides, and is not the same lesson
+        let remote = SyncData {
+            lessons: [
+                (0, record(1, "Both, renamed", vec![], time(4))),
+                (1, record(5, "Remote", vec![0], time(2))),
+                (2, record(3, "Untouched", vec![], time(1))),
+            ]
+            .into_iter()
+            .collect(),
+            deleted: [(Uid(4), time(5).unwrap())].into_iter().collect(),
+            reviews: vec![review],
+            last_sync: time(1),
+        };
+
+        let (merged, conflicts) = merge(local, remote);
+
+        let mut names = merged
+            .lessons
+            .iter()
+            .map(|(&id, record)| (id, record.lesson.name.as_str()))
+            .collect::<Vec<_>>();
+        names.sort();
+        assert_eq!(
+            names,
+            vec![
+                (0, "Both, renamed"),
+                (1, "Local"),
+                (2, "Untouched"),
+                (3, "Remote")
+            ]
+        );
+        assert_eq!(merged.deleted.keys().collect::<Vec<_>>(), vec![&Uid(4)]);
+        assert_eq!(merged.lessons[&3].lesson.direct_prerequisites, vec![0]);
+        assert_eq!(merged.reviews.len(), 2);
+        assert_eq!(
+            conflicts,
+            vec![
+                SyncConflict::EditedOnBothSides {
+                    kept: String::from("Both, renamed"),
+                    discarded: String::from("Both"),
+                },
+                SyncConflict::EditedAndDeleted {
+                    name: String::from("Edited then deleted"),
+                    kept: false,
+                },
+            ]
+        );
+    }
+}
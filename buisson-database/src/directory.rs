@@ -0,0 +1,127 @@
+//! Storing the lessons as a directory of text files, so that they can be kept in a git
+//! repository and their changes reviewed like code.
+
+use std::{
+    collections::HashMap,
+    fs::OpenOptions,
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use buisson_common::{IOBackend, Id, LessonInfo, Review};
+use ron::ser::PrettyConfig;
+
+/// The directory holding the lessons, inside the backend directory.
+const LESSONS_DIRECTORY: &str = "lessons";
+/// The file holding the reviews, inside the backend directory.
+const REVIEWS_FILENAME: &str = "reviews.log";
+
+/// A backend storing each lesson in its own RON file, `lessons/<id>.ron`, and the reviews in
+/// `reviews.log`, one per line and oldest first. Files are always written the same way, so that
+/// modifying a lesson only changes the lines of the fields that were modified, and studying it
+/// only adds a line to the reviews.
+#[derive(Debug)]
+pub struct DirectoryBackend {
+    path: PathBuf,
+}
+
+#[derive(Debug)]
+pub enum DirectoryError {
+    IOError(std::io::Error),
+    /// the file at the given path could not be parsed.
+    ParseError(PathBuf, ron::error::SpannedError),
+    SerializeError(ron::Error),
+}
+
+impl From<std::io::Error> for DirectoryError {
+    fn from(error: std::io::Error) -> Self {
+        DirectoryError::IOError(error)
+    }
+}
+
+impl DirectoryBackend {
+    /// Open the lessons stored in the directory at `path`, creating it if needed.
+    pub fn open(path: &Path) -> Result<Self, DirectoryError> {
+        std::fs::create_dir_all(path.join(LESSONS_DIRECTORY))?;
+        Ok(Self {
+            path: path.to_path_buf(),
+        })
+    }
+
+    fn lesson_path(&self, id: Id) -> PathBuf {
+        self.path.join(LESSONS_DIRECTORY).join(format!("{id}.ron"))
+    }
+
+    fn write_lesson(&self, id: Id, lesson: &LessonInfo) -> Result<(), DirectoryError> {
+        let config = PrettyConfig::new().new_line(String::from("\n"));
+        let mut text =
+            ron::ser::to_string_pretty(lesson, config).map_err(DirectoryError::SerializeError)?;
+        text.push('\n');
+        std::fs::write(self.lesson_path(id), text)?;
+        Ok(())
+    }
+}
+
+impl IOBackend for DirectoryBackend {
+    type Error = DirectoryError;
+
+    fn query_lessons(&self) -> Result<HashMap<Id, LessonInfo>, Self::Error> {
+        let mut lessons = HashMap::new();
+        for entry in std::fs::read_dir(self.path.join(LESSONS_DIRECTORY))? {
+            let path = entry?.path();
+            // other files, like a README, are not lessons
+            let id = match path.file_stem().and_then(|stem| stem.to_str()) {
+                Some(stem) if path.extension().is_some_and(|ext| ext == "ron") => {
+                    match stem.parse::<Id>() {
+                        Ok(id) => id,
+                        Err(_) => continue,
+                    }
+                }
+                _ => continue,
+            };
+            let text = std::fs::read_to_string(&path)?;
+            let lesson =
+                ron::from_str(&text).map_err(|e| DirectoryError::ParseError(path.clone(), e))?;
+            lessons.insert(id, lesson);
+        }
+        Ok(lessons)
+    }
+
+    fn add_new_lesson(&self, id: Id, lesson: &LessonInfo) -> Result<(), Self::Error> {
+        self.write_lesson(id, lesson)
+    }
+
+    fn update_existing_lesson(&self, id: Id, lesson: &LessonInfo) -> Result<(), Self::Error> {
+        self.write_lesson(id, lesson)
+    }
+
+    fn remove_lesson(&self, id: Id) -> Result<(), Self::Error> {
+        std::fs::remove_file(self.lesson_path(id))?;
+        Ok(())
+    }
+
+    fn query_reviews(&self) -> Result<Vec<Review>, Self::Error> {
+        let path = self.path.join(REVIEWS_FILENAME);
+        let text = match std::fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(vec![]),
+            Err(e) => return Err(e.into()),
+        };
+        text.lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| {
+                ron::from_str(line).map_err(|e| DirectoryError::ParseError(path.clone(), e))
+            })
+            .collect()
+    }
+
+    fn add_review(&self, review: &Review) -> Result<(), Self::Error> {
+        let line = ron::to_string(review).map_err(DirectoryError::SerializeError)?;
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.path.join(REVIEWS_FILENAME))?;
+        writeln!(file, "{line}")?;
+        Ok(())
+    }
+}
@@ -1,7 +1,15 @@
-use buisson_common::{IOBackend, Id, LessonInfo};
+use buisson_common::{
+    sync::{LessonRecord, SyncData},
+    IOBackend, Id, LessonInfo, Review,
+};
 use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
-use rusqlite::Connection;
-use std::{collections::HashMap, io::Cursor, path::Path};
+use chrono::NaiveDateTime;
+use rusqlite::{types::Type, Connection};
+use std::{collections::HashMap, io::Cursor, path::Path, str::FromStr, time::Duration};
+
+mod directory;
+
+pub use directory::{DirectoryBackend, DirectoryError};
 
 /// used to serialize the ids of the prerequisite lessons.
 fn ids_to_bytes(ids: &Vec<Id>) -> Vec<u8> {
@@ -25,6 +33,62 @@ fn ids_from_bytes(bytes: &Vec<u8>) -> Vec<Id> {
     output
 }
 
+/// The format of the modification and sync times in the database.
+const TIMESTAMP_FORMAT: &str = "%Y-%m-%dT%H:%M:%S%.f";
+
+fn format_timestamp(timestamp: NaiveDateTime) -> String {
+    timestamp.format(TIMESTAMP_FORMAT).to_string()
+}
+
+/// The current UTC time, as stored in the database.
+fn now() -> String {
+    format_timestamp(chrono::offset::Utc::now().naive_utc())
+}
+
+/// Parse `text`, read from column `column`, failing like a column of the wrong type would.
+fn parse_column<F>(text: &str, column: usize) -> rusqlite::Result<F>
+where
+    F: FromStr,
+    F::Err: std::error::Error + Send + Sync + 'static,
+{
+    text.parse()
+        .map_err(|e| rusqlite::Error::FromSqlConversionFailure(column, Type::Text, Box::new(e)))
+}
+
+/// The changes made to the schema since the creation of the `lesson` table, oldest first. They
+/// are never modified once released: new changes get a new migration instead.
+const MIGRATIONS: &[&str] = &[
+    "CREATE TABLE IF NOT EXISTS review (
+        lesson INTEGER NOT NULL,
+        date TEXT NOT NULL,
+        status TEXT
+    )",
+    "ALTER TABLE lesson ADD COLUMN lapses INTEGER NOT NULL DEFAULT 0",
+    "ALTER TABLE review ADD COLUMN grade TEXT",
+    "ALTER TABLE lesson ADD COLUMN priority TEXT NOT NULL DEFAULT 'Normal'",
+    "ALTER TABLE lesson ADD COLUMN deadline TEXT",
+    "ALTER TABLE review ADD COLUMN duration INTEGER",
+    "ALTER TABLE lesson ADD COLUMN modified TEXT",
+    "CREATE TABLE IF NOT EXISTS deleted_lesson (
+        id INTEGER PRIMARY KEY,
+        deleted TEXT NOT NULL
+    )",
+    "CREATE TABLE IF NOT EXISTS sync (date TEXT NOT NULL)",
+    "ALTER TABLE lesson ADD COLUMN uid TEXT",
+    // lessons that existed before uids get one derived from their id, so that copies of the
+    // database upgraded separately still agree on it. Their upper half is all ones, which random
+    // uids are all but certain not to have.
+    "UPDATE lesson SET uid = printf('ffffffffffffffff%016x', id)",
+    // the deleted lessons are now identified by their uid, which older deletions don't have
+    "DROP TABLE IF EXISTS deleted_lesson",
+    "CREATE TABLE IF NOT EXISTS deleted_lesson (
+        uid TEXT PRIMARY KEY,
+        deleted TEXT NOT NULL
+    )",
+    // older reviews keep a null uid: there is no telling which ones are the same in two copies
+    "ALTER TABLE review ADD COLUMN uid TEXT",
+];
+
 #[derive(Debug)]
 pub struct SQLiteBackend {
     connection: rusqlite::Connection,
@@ -44,13 +108,114 @@ impl SQLiteBackend {
             )",
             (),
         )?;
+        Self::migrate(&connection)?;
 
         Ok(Self { connection })
     }
 
+    /// Bring the schema of the database up to date, by applying the migrations it hasn't seen
+    /// yet. The number of migrations already applied is stored in the `user_version` of the
+    /// database. Each migration is applied in a transaction along with the update of the version,
+    /// so that a failed one can be tried again.
+    fn migrate(connection: &Connection) -> rusqlite::Result<()> {
+        let version: usize = connection.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+
+        for (index, migration) in MIGRATIONS.iter().enumerate().skip(version) {
+            let transaction = connection.unchecked_transaction()?;
+            transaction.execute(migration, ())?;
+            transaction.pragma_update(None, "user_version", index + 1)?;
+            transaction.commit()?;
+        }
+        Ok(())
+    }
+
+    /// Read everything that is needed to merge this database with another copy of it.
+    pub fn sync_data(&self) -> rusqlite::Result<SyncData> {
+        let mut modified = self
+            .connection
+            .prepare("SELECT id, modified FROM lesson")?
+            .query_map([], |row| {
+                let modified_text: Option<String> = row.get(1)?;
+                Ok((
+                    row.get::<_, Id>(0)?,
+                    modified_text
+                        .map(|text| parse_column(&text, 1))
+                        .transpose()?,
+                ))
+            })?
+            .collect::<Result<HashMap<Id, Option<NaiveDateTime>>, _>>()?;
+
+        let lessons = self
+            .query_lessons()?
+            .into_iter()
+            .map(|(id, lesson)| {
+                let modified = modified.remove(&id).flatten();
+                (id, LessonRecord { lesson, modified })
+            })
+            .collect();
+
+        let deleted = self
+            .connection
+            .prepare("SELECT uid, deleted FROM deleted_lesson")?
+            .query_map([], |row| {
+                let uid_text: String = row.get(0)?;
+                let deleted_text: String = row.get(1)?;
+                Ok((parse_column(&uid_text, 0)?, parse_column(&deleted_text, 1)?))
+            })?
+            .collect::<Result<HashMap<_, _>, _>>()?;
+
+        let last_sync: Option<String> =
+            self.connection
+                .query_row("SELECT MAX(date) FROM sync", [], |row| row.get(0))?;
+        let last_sync = last_sync.map(|text| parse_column(&text, 0)).transpose()?;
+
+        Ok(SyncData {
+            lessons,
+            deleted,
+            reviews: self.query_reviews()?,
+            last_sync,
+        })
+    }
+
+    /// Replace the content of the database with `data`, the result of a merge, and record that
+    /// it was synced at `synced`.
+    pub fn replace_with(&mut self, data: &SyncData, synced: NaiveDateTime) -> rusqlite::Result<()> {
+        let transaction = self.connection.transaction()?;
+
+        transaction.execute("DELETE FROM lesson", ())?;
+        for (&id, record) in &data.lessons {
+            insert_lesson(
+                &transaction,
+                id,
+                &record.lesson,
+                record.modified.map(format_timestamp),
+            )?;
+        }
+
+        transaction.execute("DELETE FROM deleted_lesson", ())?;
+        for (uid, &deleted) in &data.deleted {
+            transaction.execute(
+                "INSERT INTO deleted_lesson (uid, deleted) VALUES (?1, ?2)",
+                (uid.to_string(), format_timestamp(deleted)),
+            )?;
+        }
+
+        transaction.execute("DELETE FROM review", ())?;
+        for review in &data.reviews {
+            insert_review(&transaction, review)?;
+        }
+
+        transaction.execute(
+            "INSERT INTO sync (date) VALUES (?1)",
+            (format_timestamp(synced),),
+        )?;
+        transaction.commit()
+    }
+
     pub fn open(database_path: &Path) -> rusqlite::Result<Self> {
         if std::fs::metadata(database_path).is_ok() {
             let connection = Connection::open(database_path)?;
+            Self::migrate(&connection)?;
 
             Ok(Self { connection })
         } else {
@@ -63,17 +228,21 @@ impl IOBackend for SQLiteBackend {
     type Error = rusqlite::Error;
 
     fn query_lessons(&self) -> Result<HashMap<Id, LessonInfo>, Self::Error> {
-        let mut stmt = self
-            .connection
-            .prepare("SELECT id, name, depends_on, status, tags FROM lesson")?;
+        let mut stmt = self.connection.prepare(
+            "SELECT id, name, depends_on, status, tags, lapses, priority, deadline, uid FROM lesson",
+        )?;
 
         let lessons = stmt
             .query_map([], |row| {
                 let status_ron: String = row.get(3)?;
                 let tags_text: String = row.get(4)?;
+                let priority_ron: String = row.get(6)?;
+                let deadline_text: Option<String> = row.get(7)?;
+                let uid_text: Option<String> = row.get(8)?;
 
                 let tags = tags_text
                     .split(",")
+                    .filter(|c| !c.is_empty())
                     .map(|c| c.to_string())
                     .collect::<Vec<_>>();
 
@@ -84,6 +253,15 @@ impl IOBackend for SQLiteBackend {
                         direct_prerequisites: ids_from_bytes(&row.get(2)?),
                         status: ron::from_str(&status_ron).unwrap(),
                         tags,
+                        lapses: row.get(5)?,
+                        priority: ron::from_str(&priority_ron).unwrap(),
+                        deadline: deadline_text
+                            .map(|date| parse_column(&date, 7))
+                            .transpose()?,
+                        uid: uid_text
+                            .map(|uid| parse_column(&uid, 8))
+                            .transpose()?
+                            .unwrap_or_default(),
                     },
                 ))
             })?
@@ -93,36 +271,115 @@ impl IOBackend for SQLiteBackend {
     }
 
     fn add_new_lesson(&self, id: Id, lesson: &LessonInfo) -> Result<(), Self::Error> {
-        self.connection.execute(
-            "INSERT INTO lesson VALUES (?1, ?2, ?3, ?4, ?5)",
-            (
-                id,
-                &lesson.name,
-                &ids_to_bytes(&lesson.direct_prerequisites),
-                ron::to_string(&lesson.status).unwrap(),
-                lesson.tags.join(","),
-            ),
-        )?;
-        Ok(())
+        insert_lesson(&self.connection, id, lesson, Some(now()))
     }
 
     fn update_existing_lesson(&self, id: Id, lesson: &LessonInfo) -> Result<(), Self::Error> {
         self.connection.execute(
-            "UPDATE lesson SET name = ?2, depends_on = ?3, status = ?4, tags = ?5 WHERE id = ?1",
+            "UPDATE lesson
+            SET name = ?2, depends_on = ?3, status = ?4, tags = ?5, lapses = ?6, priority = ?7,
+                deadline = ?8, modified = ?9, uid = ?10
+            WHERE id = ?1",
             (
                 id,
                 &lesson.name,
                 &ids_to_bytes(&lesson.direct_prerequisites),
                 ron::to_string(&lesson.status).unwrap(),
                 lesson.tags.join(","),
+                lesson.lapses,
+                ron::to_string(&lesson.priority).unwrap(),
+                lesson.deadline.map(|date| date.to_string()),
+                now(),
+                lesson.uid.to_string(),
             ),
         )?;
         Ok(())
     }
 
     fn remove_lesson(&self, id: Id) -> Result<(), Self::Error> {
+        self.connection.execute(
+            "INSERT OR REPLACE INTO deleted_lesson (uid, deleted)
+            SELECT uid, ?2 FROM lesson WHERE id = ?1 AND uid IS NOT NULL",
+            (&id, now()),
+        )?;
         self.connection
             .execute("DELETE FROM lesson WHERE id = ?1", (&id,))?;
         Ok(())
     }
+
+    fn query_reviews(&self) -> Result<Vec<Review>, Self::Error> {
+        let mut stmt = self
+            .connection
+            .prepare("SELECT lesson, date, status, grade, duration, uid FROM review")?;
+
+        let reviews = stmt
+            .query_map([], |row| {
+                let date_text: String = row.get(1)?;
+                let status_ron: String = row.get(2)?;
+                let grade_ron: Option<String> = row.get(3)?;
+                let duration_millis: Option<u64> = row.get(4)?;
+                let uid_text: Option<String> = row.get(5)?;
+
+                Ok(Review {
+                    lesson: row.get(0)?,
+                    date: parse_column(&date_text, 1)?,
+                    status: ron::from_str(&status_ron).unwrap(),
+                    grade: grade_ron.map(|grade| ron::from_str(&grade).unwrap()),
+                    duration: duration_millis.map(Duration::from_millis),
+                    uid: uid_text
+                        .map(|uid| parse_column(&uid, 5))
+                        .transpose()?
+                        .unwrap_or_default(),
+                })
+            })?
+            .collect::<Result<Vec<Review>, _>>()?;
+
+        Ok(reviews)
+    }
+
+    fn add_review(&self, review: &Review) -> Result<(), Self::Error> {
+        insert_review(&self.connection, review)
+    }
+}
+
+fn insert_lesson(
+    connection: &Connection,
+    id: Id,
+    lesson: &LessonInfo,
+    modified: Option<String>,
+) -> rusqlite::Result<()> {
+    connection.execute(
+        "INSERT INTO lesson
+            (id, name, depends_on, status, tags, lapses, priority, deadline, modified, uid)
+        VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+        (
+            id,
+            &lesson.name,
+            &ids_to_bytes(&lesson.direct_prerequisites),
+            ron::to_string(&lesson.status).unwrap(),
+            lesson.tags.join(","),
+            lesson.lapses,
+            ron::to_string(&lesson.priority).unwrap(),
+            lesson.deadline.map(|date| date.to_string()),
+            modified,
+            lesson.uid.to_string(),
+        ),
+    )?;
+    Ok(())
+}
+
+fn insert_review(connection: &Connection, review: &Review) -> rusqlite::Result<()> {
+    connection.execute(
+        "INSERT INTO review (lesson, date, status, grade, duration, uid)
+        VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        (
+            review.lesson,
+            review.date.to_string(),
+            ron::to_string(&review.status).unwrap(),
+            review.grade.map(|grade| ron::to_string(&grade).unwrap()),
+            review.duration.map(|duration| duration.as_millis() as u64),
+            (!review.uid.is_nil()).then(|| review.uid.to_string()),
+        ),
+    )?;
+    Ok(())
 }
@@ -1,29 +1,55 @@
-use std::collections::HashMap;
+use std::{
+    cell::Cell,
+    collections::{HashMap, HashSet},
+    time::Instant,
+};
 
-use crossterm::event::{Event, KeyCode, KeyEvent, KeyEventKind};
+use cli_log::warn;
+use crossterm::event::{
+    Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers, MouseButton, MouseEvent, MouseEventKind,
+};
 use rand::{rngs::ThreadRng, thread_rng};
 use ratatui::{
-    layout::{Alignment, Constraint, Layout, Rect},
+    layout::{Alignment, Constraint, Layout, Position, Rect},
     style::{Style, Stylize},
     text::{Line, Span, Text},
-    widgets::{Block, Borders, Clear, List, ListItem, Paragraph},
+    widgets::{Block, Borders, Clear, List, ListItem, Paragraph, Wrap},
     Frame,
 };
 
 const DB_FILENAME: &str = "lessons.sqlite";
+const CONFIG_FILENAME: &str = "config.ron";
+const SESSION_FILENAME: &str = "session.ron";
 
 use crate::{
     components::{
+        bulk_actions::{
+            BulkAction, BulkActionMenu, BulkActionMenuAction, TagPrompt, TagPromptAction,
+        },
         fuzzyfinder::{FuzzyFinder, FuzzyFinderAction},
+        help::{HelpOverlay, HelpOverlayAction},
+        learning_path::{LearningPath, LearningPathAction},
         lesson_edit_form::{LessonEditForm, LessonEditFormAction},
+        lesson_splitter::{LessonSplitter, LessonSplitterAction},
+        lesson_tree::{LessonTree, LessonTreeAction},
         node_list::NodeList,
+        review_prompt::{ReviewPrompt, ReviewPromptAction},
+        sort_selector::{SortSelector, SortSelectorAction},
+        statistics::{format_duration, StatisticsScreen, StatisticsScreenAction},
         study_editor::{StudyEditor, StudyEditorAction},
     },
-    style_from_status,
+    config::{Config, ConfigError},
+    keymap::{Action, KeyContext, Keymap},
+    session::Session,
+    storage::{Storage, StorageError},
+    theme::Theme,
 };
 
-use buisson_common::{Graph, GraphNode, Id, LessonInfo, LessonStatus};
-use buisson_database::SQLiteBackend;
+use buisson_common::{
+    goal::GoalProgress, grade, query::QueryParseError, BuissonError, Graph, GraphNode, Id,
+    LessonInfo, LessonStatus, NodeStatus,
+};
+use buisson_database::{DirectoryBackend, SQLiteBackend};
 
 /// The state of the main application
 enum AppState {
@@ -31,8 +57,25 @@ enum AppState {
     AddingNewLesson(LessonEditForm),
     EditingLesson(Id, LessonEditForm),
     ConfirmingDeletion(Id),
-    Studying(Id, StudyEditor),
+    /// Studying a lesson, since the given instant.
+    Studying(Id, Instant, StudyEditor),
     Searching(FuzzyFinder),
+    ExploringTree(LessonTree),
+    PlanningPath(LearningPath),
+    ChoosingSort(SortSelector),
+    ShowingStatistics(StatisticsScreen),
+    PromptingReview(ReviewPrompt),
+    ChoosingBulkAction(BulkActionMenu),
+    /// Asking for the tag to add to or remove from the marked lessons.
+    EnteringBulkTag(BulkAction, TagPrompt),
+    /// Choosing a lesson to add as a prerequisite of all the marked lessons.
+    ChoosingBulkPrerequisite(FuzzyFinder),
+    ConfirmingBulkDeletion(Vec<Id>),
+    /// Choosing a lesson to merge into the given one.
+    ChoosingMergedLesson(Id, FuzzyFinder),
+    /// Confirming the merge of the second lesson into the first one.
+    ConfirmingMerge(Id, Id),
+    SplittingLesson(Id, LessonSplitter),
     Quitting,
 }
 
@@ -40,45 +83,158 @@ enum AppState {
 pub enum AppError {
     IOError(std::io::Error),
     SQLiteError(rusqlite::Error),
+    StorageError(StorageError),
+    /// an operation on the lessons failed.
+    LessonError(BuissonError<Storage>),
     XDGError(xdg::BaseDirectoriesError),
+    ConfigError(ConfigError),
+    InvalidQuery(QueryParseError),
+    /// the program was called with arguments that don't correspond to any command.
+    InvalidCommand(String),
 }
 
 pub struct App {
-    lessons: Graph<SQLiteBackend>,
+    lessons: Graph<Storage>,
     main_list: NodeList,
     state: AppState,
     rng: ThreadRng,
+    keymap: Keymap,
+    theme: Theme,
+    session: Session,
+    /// how many lapses it takes for a lesson to be considered a leech.
+    leech_threshold: u32,
+    /// whether to offer to review the prerequisites of a failed lesson early.
+    review_prerequisites_of_failed: bool,
+    /// the pace used to estimate how long learning paths take.
+    lessons_per_day: u32,
+    /// the progress of the goals, updated whenever the lessons change rather than on every frame.
+    goals: Vec<GoalProgress>,
+    /// the learning path a lesson is being studied from, to go back to once it is done.
+    suspended_path: Option<LearningPath>,
+    /// where the prerequisites of the selected lesson were last drawn in the side panel, one per
+    /// row, to find the one under a mouse click
+    side_panel_prerequisites: Cell<Rect>,
+    /// the list of the keys available in the current state, when it is shown over it
+    help: Option<HelpOverlay>,
+    /// the error of the last operation on the lessons, shown until the next key is pressed
+    error: Option<BuissonError<Storage>>,
 }
 
 #[derive(Debug, Clone)]
 pub struct Context<'a> {
     pub lessons: &'a HashMap<Id, GraphNode>,
+    /// for every lesson, the lessons that have it as a direct prerequisite
+    pub children: &'a HashMap<Id, Vec<Id>>,
+    pub keymap: &'a Keymap,
+    pub theme: &'a Theme,
+}
+
+/// Return a `width` by `height` rectangle at the center of `area`, shrunk to fit in it if needed.
+fn centered_rect(area: Rect, width: u16, height: u16) -> Rect {
+    let width = std::cmp::min(width, area.width);
+    let height = std::cmp::min(height, area.height);
+    Rect {
+        x: area.x + (area.width - width) / 2,
+        y: area.y + (area.height - height) / 2,
+        width,
+        height,
+    }
+}
+
+/// Open the database in the XDG data directory, creating it if needed.
+pub fn open_database() -> Result<SQLiteBackend, AppError> {
+    let directories = xdg::BaseDirectories::with_prefix("buisson").map_err(AppError::XDGError)?;
+    let data_path = directories.get_data_home();
+    std::fs::create_dir_all(data_path).map_err(AppError::IOError)?;
+    let database_path = directories.get_data_home().join(DB_FILENAME);
+
+    SQLiteBackend::open(&database_path).map_err(AppError::SQLiteError)
+}
+
+/// Read the configuration in the XDG config directory.
+pub fn load_config() -> Result<Config, AppError> {
+    let directories = xdg::BaseDirectories::with_prefix("buisson").map_err(AppError::XDGError)?;
+    let config_path = directories.get_config_home().join(CONFIG_FILENAME);
+    Config::load(&config_path).map_err(AppError::ConfigError)
+}
+
+/// Open the lessons where `config` says they are stored: in the directory it sets, or else in the
+/// database in the XDG data directory, creating it if needed.
+pub fn open_lessons(config: &Config) -> Result<Graph<Storage>, AppError> {
+    let storage = match &config.lessons_directory {
+        Some(directory) => Storage::Directory(
+            DirectoryBackend::open(directory)
+                .map_err(|e| AppError::StorageError(StorageError::Directory(e)))?,
+        ),
+        None => Storage::Database(open_database()?),
+    };
+    Graph::get_from_database(storage).map_err(AppError::StorageError)
 }
 
 impl App {
     pub fn new() -> Result<Self, AppError> {
         let directories =
             xdg::BaseDirectories::with_prefix("buisson").map_err(AppError::XDGError)?;
-        let data_path = directories.get_data_home();
-        std::fs::create_dir_all(data_path).map_err(AppError::IOError)?;
-        let database_path = directories.get_data_home().join(DB_FILENAME);
 
-        let backend = SQLiteBackend::open(&database_path).map_err(AppError::SQLiteError)?;
+        let config = load_config()?;
+        let lessons = open_lessons(&config)?;
 
-        let lessons = Graph::get_from_database(backend).map_err(AppError::SQLiteError)?;
-        let lesson_ids = lessons.get_ids();
+        let session = Session::load(&directories.get_data_home().join(SESSION_FILENAME));
+        Self::with_lessons(lessons, config, session)
+    }
+
+    /// Start the application on `lessons`, without reading anything from disk.
+    fn with_lessons(
+        lessons: Graph<Storage>,
+        config: Config,
+        session: Session,
+    ) -> Result<Self, AppError> {
+        let lesson_ids = lessons.sorted_ids(session.sort_order);
+        let today = chrono::offset::Local::now().date_naive();
+        let goals = lessons
+            .goals(today, config.lessons_per_day)
+            .map_err(AppError::LessonError)?;
+        let keymap = Keymap::with_overrides(config.keybindings).map_err(AppError::ConfigError)?;
 
         Ok(Self {
             lessons,
             main_list: NodeList::new(lesson_ids),
             state: AppState::BrowsingLessons,
             rng: thread_rng(),
+            keymap,
+            theme: Theme::new(config.theme, config.status_glyphs),
+            session,
+            leech_threshold: config.leech_threshold,
+            review_prerequisites_of_failed: config.review_prerequisites_of_failed,
+            lessons_per_day: config.lessons_per_day,
+            goals,
+            suspended_path: None,
+            side_panel_prerequisites: Cell::default(),
+            help: None,
+            error: None,
         })
     }
 
+    /// Sort the main list again and update the goals, after lessons were modified. The selected
+    /// lesson stays selected.
+    fn refresh_main_list(&mut self) {
+        let today = chrono::offset::Local::now().date_naive();
+        self.goals = self.lessons.goals(today, self.lessons_per_day).unwrap();
+
+        let selected = self.main_list.currently_selected_id();
+        self.main_list
+            .change_values(self.lessons.sorted_ids(self.session.sort_order));
+        if let Some(id) = selected {
+            self.main_list.select(id);
+        }
+    }
+
     fn get_context(&self) -> Context<'_> {
         Context {
             lessons: self.lessons.lessons(),
+            children: self.lessons.children(),
+            keymap: &self.keymap,
+            theme: &self.theme,
         }
     }
 
@@ -131,12 +287,72 @@ impl App {
             AppState::BrowsingLessons => {
                 self.render_side_panel(right_panel_minus_bar, frame);
             }
+            AppState::ExploringTree(tree) => {
+                tree.render(self.get_context(), right_panel_minus_bar, frame);
+            }
+            AppState::PlanningPath(path) => {
+                path.render(self.get_context(), right_panel_minus_bar, frame);
+            }
+            AppState::ChoosingSort(selector) => {
+                self.render_side_panel(right_panel_minus_bar, frame);
+                let (width, height) = SortSelector::size();
+                let popup_area = centered_rect(left_panel_minus_bar, width, height);
+                frame.render_widget(Clear, popup_area);
+                selector.render(popup_area, frame);
+            }
+            AppState::ChoosingBulkAction(menu) => {
+                self.render_side_panel(right_panel_minus_bar, frame);
+                let (width, height) = menu.size();
+                let popup_area = centered_rect(left_panel_minus_bar, width, height);
+                frame.render_widget(Clear, popup_area);
+                menu.render(popup_area, frame);
+            }
+            AppState::EnteringBulkTag(_, prompt) => {
+                self.render_side_panel(right_panel_minus_bar, frame);
+                let (width, height) = prompt.size();
+                let popup_area = centered_rect(left_panel_minus_bar, width, height);
+                frame.render_widget(Clear, popup_area);
+                prompt.render(popup_area, frame);
+            }
+            AppState::ChoosingBulkPrerequisite(finder) => {
+                frame.render_widget(Clear, fuzzy_finder_area);
+                self.render_help(right_panel_minus_bar, frame);
+                finder.render(self.get_context(), fuzzy_finder_area, frame);
+            }
+            AppState::ConfirmingBulkDeletion(ids) => {
+                self.render_side_panel(right_panel_minus_bar, frame);
+                self.render_bulk_deletion_confirmation_popup(ids, left_panel_minus_bar, frame);
+            }
+            AppState::ChoosingMergedLesson(_, finder) => {
+                frame.render_widget(Clear, fuzzy_finder_area);
+                self.render_help(right_panel_minus_bar, frame);
+                finder.render(self.get_context(), fuzzy_finder_area, frame);
+            }
+            AppState::ConfirmingMerge(id, other) => {
+                self.render_side_panel(right_panel_minus_bar, frame);
+                self.render_merge_confirmation_popup(*id, *other, left_panel_minus_bar, frame);
+            }
+            AppState::SplittingLesson(_, splitter) => {
+                splitter.render(self.get_context(), right_panel_minus_bar, frame);
+            }
+            AppState::PromptingReview(prompt) => {
+                self.render_side_panel(right_panel_minus_bar, frame);
+                let (width, height) = prompt.size(self.get_context());
+                let popup_area = centered_rect(left_panel_minus_bar, width, height);
+                frame.render_widget(Clear, popup_area);
+                prompt.render(self.get_context(), popup_area, frame);
+            }
+            AppState::ShowingStatistics(statistics) => {
+                let statistics_area = left_panel_minus_bar.union(right_panel_minus_bar);
+                frame.render_widget(Clear, statistics_area);
+                statistics.render(self.get_context(), statistics_area, frame);
+            }
             AppState::Searching(search_input) => {
                 frame.render_widget(Clear, fuzzy_finder_area);
                 self.render_help(right_panel_minus_bar, frame);
                 search_input.render(self.get_context(), fuzzy_finder_area, frame);
             }
-            AppState::Studying(_, study_editor) => {
+            AppState::Studying(_, started, study_editor) => {
                 let horizontal_area =
                     Layout::horizontal(Constraint::from_percentages([30, 40, 30])).split(area)[1];
                 let top_padding = (horizontal_area.height - 5) / 2;
@@ -147,6 +363,7 @@ impl App {
 
                 let block = Block::new()
                     .title("Study")
+                    .title(Line::from(format_duration(started.elapsed())).right_aligned())
                     .borders(Borders::ALL)
                     .border_style(Style::default().bold());
 
@@ -154,7 +371,7 @@ impl App {
 
                 frame.render_widget(Clear, vertical_area);
                 frame.render_widget(block, vertical_area);
-                study_editor.render(study_editor_area, frame);
+                study_editor.render(&self.keymap, study_editor_area, frame);
             }
             AppState::ConfirmingDeletion(id_to_delete) => {
                 self.render_side_panel(right_panel_minus_bar, frame);
@@ -163,6 +380,13 @@ impl App {
                 }
             }
         }
+
+        if let Some(help) = &self.help {
+            let (width, height) = help.size(&self.keymap);
+            let popup_area = centered_rect(area, width, height);
+            frame.render_widget(Clear, popup_area);
+            help.render(&self.keymap, popup_area, frame);
+        }
     }
 
     fn render_deletion_confirmation_popup(
@@ -177,10 +401,16 @@ impl App {
             "Confirm deletion : {}",
             self.lessons.get(*id_to_delete).unwrap().lesson.name
         );
+        let keys = self.deletion_confirmation_keys(self.can_reconnect(*id_to_delete));
+
+        let text_width = std::cmp::max(
+            unicode_width::UnicodeWidthStr::width(confirmation_message.as_str()),
+            unicode_width::UnicodeWidthStr::width(keys.as_str()),
+        );
 
         let num_cols_needed: u16 = 2 // block border
                                  + 2 // some padding
-                                 + unicode_width::UnicodeWidthStr::width(confirmation_message.as_str()) as u16;
+                                 + text_width as u16;
 
         let actual_width = area.width - 4;
         let popup_width = std::cmp::min(actual_width, num_cols_needed);
@@ -212,7 +442,7 @@ impl App {
 
             let lines = vec![
                 Line::from(vec![Span::raw(confirmation_message)]),
-                Line::from(vec![Span::raw("Y/n")]),
+                Line::from(vec![Span::raw(keys.clone())]),
             ];
             frame.render_widget(Clear, popup_area);
             let widget = Paragraph::new(lines).block(Block::new().borders(Borders::ALL));
@@ -231,7 +461,7 @@ impl App {
             let lines = vec![
                 Line::from(vec![Span::raw(confirmation_message)]),
                 Line::default(),
-                Line::from(vec![Span::raw("Y/n")]),
+                Line::from(vec![Span::raw(keys.clone())]),
             ];
             frame.render_widget(Clear, popup_area);
             let widget = Paragraph::new(lines).block(Block::new().borders(Borders::ALL));
@@ -253,7 +483,7 @@ impl App {
                     "There are {} lessons depending on it",
                     children_id.len()
                 ))]),
-                Line::from(vec![Span::raw("Y/n")]),
+                Line::from(vec![Span::raw(keys.clone())]),
             ];
             frame.render_widget(Clear, popup_area);
             let widget = Paragraph::new(lines).block(Block::new().borders(Borders::ALL));
@@ -276,7 +506,7 @@ impl App {
                     children_id.len()
                 ))]),
                 Line::default(),
-                Line::from(vec![Span::raw("Y/n")]),
+                Line::from(vec![Span::raw(keys.clone())]),
             ];
             let widget = Paragraph::new(lines).block(Block::new().borders(Borders::ALL));
             frame.render_widget(Clear, popup_area);
@@ -302,12 +532,10 @@ impl App {
 
             lines.extend(children_id.iter().map(|id| {
                 let child_node = self.lessons.get(*id).unwrap();
-                Line::from(vec![Span::styled(
-                    &child_node.lesson.name,
-                    style_from_status(&child_node.status),
-                )])
+                self.theme
+                    .lesson_name(&child_node.lesson.name, &child_node.status)
             }));
-            lines.push(Line::from(vec![Span::raw("Y/n")]));
+            lines.push(Line::from(vec![Span::raw(keys)]));
             let widget = Paragraph::new(lines).block(Block::new().borders(Borders::ALL));
             frame.render_widget(Clear, popup_area);
             frame.render_widget(widget, popup_area);
@@ -316,6 +544,110 @@ impl App {
         true
     }
 
+    /// Ask for confirmation before deleting every lesson of `ids`, listing as many of them as
+    /// `area` allows.
+    fn render_bulk_deletion_confirmation_popup(
+        &self,
+        ids: &[Id],
+        area: Rect,
+        frame: &mut Frame<'_>,
+    ) {
+        // the lessons that will lose a prerequisite
+        let num_dependents = ids
+            .iter()
+            .flat_map(|&id| self.lessons.get_children(id).unwrap())
+            .filter(|child| !ids.contains(child))
+            .collect::<HashSet<_>>()
+            .len();
+
+        let mut lines = vec![Line::from(format!(
+            "Confirm deletion of {} lessons:",
+            ids.len()
+        ))];
+        // borders, the header, the footer and the line telling how many lessons were not listed
+        let max_listed = (area.height as usize).saturating_sub(6);
+        lines.extend(ids.iter().take(max_listed).map(|&id| {
+            let node = self.lessons.get(id).unwrap();
+            self.theme.lesson_name(&node.lesson.name, &node.status)
+        }));
+        if ids.len() > max_listed {
+            lines.push(Line::from(format!("and {} more", ids.len() - max_listed)));
+        }
+        if num_dependents > 0 {
+            lines.push(Line::from(format!(
+                "{} other lessons depend on them",
+                num_dependents
+            )));
+        }
+        lines.push(Line::from(self.deletion_confirmation_keys(false)));
+
+        let width = lines.iter().map(Line::width).max().unwrap_or_default() as u16 + 4;
+        let popup_area = centered_rect(area, width, lines.len() as u16 + 2);
+        let widget = Paragraph::new(lines).block(Block::new().borders(Borders::ALL));
+        frame.render_widget(Clear, popup_area);
+        frame.render_widget(widget, popup_area);
+    }
+
+    /// Ask for confirmation before merging `other` into `id`.
+    fn render_merge_confirmation_popup(
+        &self,
+        id: Id,
+        other: Id,
+        area: Rect,
+        frame: &mut Frame<'_>,
+    ) {
+        let name = &self.lessons.get(id).unwrap().lesson.name;
+        let other_name = &self.lessons.get(other).unwrap().lesson.name;
+        let lines = vec![
+            Line::from(format!("Merge \"{other_name}\" into \"{name}\"?")),
+            Line::from(format!(
+                "\"{other_name}\" is deleted, and its prerequisites, dependents and tags go to \
+                 \"{name}\""
+            )),
+            Line::default(),
+            Line::from(self.deletion_confirmation_keys(false)),
+        ];
+
+        let width = lines.iter().map(Line::width).max().unwrap_or_default() as u16 + 4;
+        let popup_area = centered_rect(area, width, lines.len() as u16 + 2);
+        let widget = Paragraph::new(lines).block(Block::new().borders(Borders::ALL));
+        frame.render_widget(Clear, popup_area);
+        frame.render_widget(widget, popup_area);
+    }
+
+    /// Whether deleting `id` while reconnecting its dependents differs from simply deleting it.
+    fn can_reconnect(&self, id: Id) -> bool {
+        !self.lessons.get_children(id).unwrap().is_empty()
+            && !self
+                .lessons
+                .get(id)
+                .unwrap()
+                .lesson
+                .direct_prerequisites
+                .is_empty()
+    }
+
+    /// the keys to confirm or cancel a deletion, in the form "Y/n". With `reconnect`, the key to
+    /// delete while reconnecting the dependents is given too.
+    fn deletion_confirmation_keys(&self, reconnect: bool) -> String {
+        let first_key = |action| {
+            self.keymap
+                .keys(KeyContext::DeletionConfirmation, action)
+                .first()
+                .map(|key| key.to_string())
+                .unwrap_or_default()
+        };
+        let keys = [Action::Confirm, Action::Cancel].map(first_key).join("/");
+        if reconnect {
+            format!(
+                "{keys}, or {} to make its dependents require its prerequisites",
+                first_key(Action::DeleteReconnecting)
+            )
+        } else {
+            keys
+        }
+    }
+
     fn render_status_line_deletion_confirmation(
         &self,
         id_to_delete: &Id,
@@ -324,8 +656,9 @@ impl App {
     ) {
         frame.render_widget(
             Text::from(format!(
-                "Confirm deletion of lesson \"{}\"? Y/n",
-                self.lessons.get(*id_to_delete).unwrap().lesson.name
+                "Confirm deletion of lesson \"{}\"? {}",
+                self.lessons.get(*id_to_delete).unwrap().lesson.name,
+                self.deletion_confirmation_keys(self.can_reconnect(*id_to_delete))
             )),
             area,
         )
@@ -342,21 +675,41 @@ impl App {
                 good_until,
             } => format!("Step : {} - Good Until {}", level, good_until),
         };
-        let style = style_from_status(&node.status);
+        let style = self.theme.style_from_status(&node.status);
         let mut text = vec![
             Line::default(),
             Line::from(vec![Span::raw(step_text)]),
+            Line::from(format!("Priority : {:?}", node.lesson.priority)),
+        ];
+        if let Some(deadline) = node.lesson.deadline {
+            text.push(Line::from(format!("Goal : before {}", deadline)));
+        }
+        text.extend([
             Line::default(),
             Line::from(vec![Span::raw("Prerequisites: ")]),
-        ];
+        ]);
 
-        text.extend(node.lesson.direct_prerequisites.iter().map(|id| {
+        // the prerequisites are drawn apart, one per row, so that they can be clicked
+        let prerequisites = node.lesson.direct_prerequisites.iter().map(|id| {
             let prereq_node = self.lessons.get(*id).unwrap();
-            Line::from(vec![Span::styled(
-                &prereq_node.lesson.name,
-                style_from_status(&prereq_node.status),
-            )])
-        }));
+            ListItem::new(
+                self.theme
+                    .lesson_name(&prereq_node.lesson.name, &prereq_node.status),
+            )
+        });
+
+        let mut warning = vec![];
+        if node.lesson.lapses >= self.leech_threshold {
+            warning.extend([
+                Line::default(),
+                Line::from(format!(
+                    "This lesson was forgotten {} times. Consider splitting it into smaller \
+                     lessons, or adding the prerequisites it is missing.",
+                    node.lesson.lapses
+                ))
+                .italic(),
+            ]);
+        }
 
         let block = Block::new()
             .title(node.lesson.name.as_str())
@@ -364,18 +717,46 @@ impl App {
             .border_style(style.bold())
             .borders(Borders::ALL);
 
-        let widget = Paragraph::new(text).style(Style::new().white());
-
         let inner = block.inner(area);
 
-        let layout =
-            Layout::vertical([Constraint::Percentage(100), Constraint::Min(1)]).split(inner);
+        let info = Paragraph::new(text)
+            .style(Style::new().white())
+            .wrap(Wrap { trim: true });
+
+        let layout = Layout::vertical([
+            Constraint::Length(info.line_count(inner.width) as u16),
+            Constraint::Length(node.lesson.direct_prerequisites.len() as u16),
+            Constraint::Percentage(100),
+            Constraint::Min(1),
+        ])
+        .split(inner);
 
         frame.render_widget(block, area);
 
-        frame.render_widget(widget, layout[0]);
+        frame.render_widget(info, layout[0]);
+
+        frame.render_widget(List::new(prerequisites), layout[1]);
+        self.side_panel_prerequisites.set(layout[1]);
 
-        frame.render_widget(Text::from("Type 'e' to edit this lesson"), layout[1]);
+        frame.render_widget(
+            Paragraph::new(warning)
+                .style(Style::new().white())
+                .wrap(Wrap { trim: true }),
+            layout[2],
+        );
+
+        frame.render_widget(
+            Text::from(format!(
+                "Type {} to edit this lesson, {} to explore its dependencies, {} to list every key",
+                self.keymap
+                    .describe_keys(KeyContext::Browsing, Action::EditLesson),
+                self.keymap
+                    .describe_keys(KeyContext::Browsing, Action::OpenTree),
+                self.keymap
+                    .describe_keys(KeyContext::Help, Action::ShowHelp)
+            )),
+            layout[3],
+        );
     }
 
     /// renders help to `area`. Things like keybindings, etc...
@@ -386,7 +767,24 @@ impl App {
             .borders(Borders::ALL)
             .border_style(Style::new().bold());
 
-        let help_text = Paragraph::new("Type 'q' to quit")
+        let lines = [KeyContext::Browsing, KeyContext::List]
+            .into_iter()
+            .flat_map(|context| {
+                self.keymap
+                    .bindings(context)
+                    .iter()
+                    .filter(|(_, keys)| !keys.is_empty())
+                    .map(move |(action, _)| {
+                        Line::from(format!(
+                            "Type {} to {}",
+                            self.keymap.describe_keys(context, *action),
+                            action.description()
+                        ))
+                    })
+            })
+            .collect::<Vec<_>>();
+
+        let help_text = Paragraph::new(lines)
             .block(block)
             .style(Style::new().white());
 
@@ -394,6 +792,7 @@ impl App {
     }
 
     fn render_side_panel(&self, area: Rect, frame: &mut Frame<'_>) {
+        self.side_panel_prerequisites.set(Rect::default());
         if let Some(id) = self.main_list.currently_selected_id() {
             self.render_node_display(area, frame, self.lessons.get(id).unwrap());
         } else {
@@ -402,19 +801,40 @@ impl App {
     }
 
     fn render_status_line(&self, area: Rect, frame: &mut Frame<'_>) {
+        if let Some(e) = &self.error {
+            frame.render_widget(
+                Line::from(format!(" {}", e.to_string().trim_end())).red(),
+                area,
+            );
+            return;
+        }
+
         let num_ok_lessons = self.lessons.num_ok_nodes();
         let num_lessons = self.lessons.num_nodes();
         let percent_ok_lessons = (num_ok_lessons as f64 / num_lessons as f64) * 100.0;
         let average_step = self.lessons.average_step();
         let busyness = self.lessons.expected_num_lesson_per_day();
 
-        frame.render_widget(
-            Text::from(format!(
-                " OK Lessons : {}/{} ({:.2}%)    Average Step: {:.3}    Load: {:.3}",
-                num_ok_lessons, num_lessons, percent_ok_lessons, average_step, busyness
-            )),
-            area,
-        );
+        let mut spans = vec![Span::raw(format!(
+            " OK Lessons : {}/{} ({:.2}%)    Average Step: {:.3}    Load: {:.3}",
+            num_ok_lessons, num_lessons, percent_ok_lessons, average_step, busyness
+        ))];
+
+        if let Some(goal) = self.goals.iter().find(|goal| goal.at_risk) {
+            spans.push(Span::styled(
+                format!(
+                    "    Goal \"{}\" at risk: {} lessons in {} days, study {} a day",
+                    self.lessons.get(goal.id).unwrap().lesson.name,
+                    goal.remaining,
+                    goal.days_left,
+                    goal.needed_per_day
+                ),
+                self.theme
+                    .style_from_status(&NodeStatus::MissingPrereq(vec![])),
+            ));
+        }
+
+        frame.render_widget(Line::from(spans), area);
     }
 
     fn render_lessons_list(&self, area: Rect, frame: &mut Frame<'_>) {
@@ -422,15 +842,25 @@ impl App {
             AppState::BrowsingLessons | AppState::Searching(_) => Style::default().bold(),
             _ => Style::default(),
         };
+        let mut title = format!("Lessons (by {})", self.session.sort_order.description());
+        let marked = self.main_list.marked_set();
+        if !marked.is_empty() {
+            title.push_str(&format!(" - {} marked", marked.len()));
+        }
         let block = Block::new()
-            .title(Line::from("Lessons").alignment(Alignment::Center))
+            .title(Line::from(title).alignment(Alignment::Center))
             .borders(Borders::ALL)
             .style(border_style);
 
+        self.main_list.set_area(block.inner(area));
         let list_widget = List::new(self.main_list.ids().iter().map(|id| {
             let node = self.lessons.get(*id).unwrap();
-            let text = Text::from(node.lesson.name.as_str());
-            ListItem::new(text).style(style_from_status(&node.status))
+            let mut line = self.theme.lesson_name(&node.lesson.name, &node.status);
+            if !marked.is_empty() {
+                let marker = if marked.contains(id) { "* " } else { "  " };
+                line.spans.insert(0, Span::raw(marker).bold());
+            }
+            ListItem::new(line)
         }))
         .block(block)
         .highlight_style(Style::default().reversed());
@@ -438,7 +868,10 @@ impl App {
         match self.state {
             AppState::BrowsingLessons
             | AppState::EditingLesson(_, _)
-            | AppState::Studying(_, _) => {
+            | AppState::SplittingLesson(_, _)
+            | AppState::Studying(_, _, _)
+            | AppState::ExploringTree(_)
+            | AppState::PlanningPath(_) => {
                 frame.render_stateful_widget(
                     list_widget,
                     area,
@@ -452,8 +885,15 @@ impl App {
     }
 
     pub fn handle_event(&mut self, event: &Event) {
-        if let Event::Key(key) = event {
-            self.handle_key(key);
+        // the help overlay only handles keys
+        if self.help.is_some() && !matches!(event, Event::Key(_)) {
+            return;
+        }
+        match event {
+            Event::Key(key) => self.handle_key(key),
+            Event::Mouse(mouse) => self.handle_mouse(mouse),
+            Event::Paste(text) => self.handle_paste(text),
+            _ => (),
         }
     }
 }
@@ -464,74 +904,475 @@ impl App {
         if key.kind != KeyEventKind::Press {
             return;
         }
+        self.error = None;
+
+        if let Some(help) = &mut self.help {
+            if let HelpOverlayAction::Terminate = help.handle_key(&self.keymap, key) {
+                self.help = None;
+            }
+            return;
+        }
+        let contexts = self.key_contexts();
+        // characters are typed in text fields rather than opening the help
+        let typed = contexts.contains(&KeyContext::TextInput)
+            && matches!(key.code, KeyCode::Char(_))
+            && key.modifiers.difference(KeyModifiers::SHIFT).is_empty();
+        if !typed && self.keymap.action(KeyContext::Help, key) == Some(Action::ShowHelp) {
+            self.help = Some(HelpOverlay::new(contexts));
+            return;
+        }
+
+        let context = Context {
+            lessons: self.lessons.lessons(),
+            children: self.lessons.children(),
+            keymap: &self.keymap,
+            theme: &self.theme,
+        };
 
         match &mut self.state {
             AppState::BrowsingLessons => self.handle_key_browsing(key),
-            AppState::AddingNewLesson(event_name) => match event_name.handle_key(key) {
-                LessonEditFormAction::Terminate(Some(lesson_info)) => {
-                    let id = self.lessons.create_new_node(lesson_info).unwrap();
-                    self.main_list.push(id);
+            AppState::AddingNewLesson(form) | AppState::EditingLesson(_, form) => {
+                let action = form.handle_key(context, key);
+                self.handle_lesson_form_action(action);
+            }
+            AppState::Searching(finder) => {
+                let action = finder.handle_key(context, key);
+                self.handle_search_action(action);
+            }
+            AppState::ChoosingBulkAction(menu) => match menu.handle_key(&self.keymap, key) {
+                BulkActionMenuAction::Terminate(Some(action)) => self.start_bulk_action(action),
+                BulkActionMenuAction::Terminate(None) => self.state = AppState::BrowsingLessons,
+                BulkActionMenuAction::Noop => (),
+            },
+            AppState::EnteringBulkTag(action, prompt) => {
+                let adding = *action == BulkAction::AddTag;
+                match prompt.handle_key(&self.keymap, key) {
+                    TagPromptAction::Terminate(Some(tag)) => {
+                        let targets = self.bulk_targets();
+                        if adding {
+                            self.edit_lessons(&targets, |lesson| {
+                                let missing = !lesson.tags.contains(&tag);
+                                if missing {
+                                    lesson.tags.push(tag.clone());
+                                }
+                                missing
+                            });
+                        } else {
+                            self.edit_lessons(&targets, |lesson| {
+                                let num_tags = lesson.tags.len();
+                                lesson.tags.retain(|t| *t != tag);
+                                lesson.tags.len() != num_tags
+                            });
+                        }
+                        self.state = AppState::BrowsingLessons;
+                    }
+                    TagPromptAction::Terminate(None) => self.state = AppState::BrowsingLessons,
+                    TagPromptAction::Noop => (),
+                }
+            }
+            AppState::ChoosingBulkPrerequisite(finder) => match finder.handle_key(context, key) {
+                FuzzyFinderAction::Terminate(Some(prerequisite)) => {
+                    let targets = self.bulk_targets();
+                    self.edit_lessons(&targets, |lesson| {
+                        let missing = !lesson.direct_prerequisites.contains(&prerequisite);
+                        if missing {
+                            lesson.direct_prerequisites.push(prerequisite);
+                        }
+                        missing
+                    });
                     self.state = AppState::BrowsingLessons;
+                    self.refresh_main_list();
+                }
+                FuzzyFinderAction::Terminate(None) => self.state = AppState::BrowsingLessons,
+                FuzzyFinderAction::MarkAll(_) | FuzzyFinderAction::Noop => (),
+            },
+            AppState::ChoosingMergedLesson(id, finder) => match finder.handle_key(context, key) {
+                FuzzyFinderAction::Terminate(Some(other)) => {
+                    self.state = AppState::ConfirmingMerge(*id, other);
                 }
-                LessonEditFormAction::Terminate(None) => self.state = AppState::BrowsingLessons,
-                LessonEditFormAction::Noop => (),
+                FuzzyFinderAction::Terminate(None) => self.state = AppState::BrowsingLessons,
+                FuzzyFinderAction::MarkAll(_) | FuzzyFinderAction::Noop => (),
             },
-            AppState::EditingLesson(id, lesson) => match lesson.handle_key(key) {
-                LessonEditFormAction::Terminate(Some(lesson_info)) => {
-                    self.lessons.edit_node(*id, lesson_info).unwrap();
+            AppState::ConfirmingMerge(id, other) => {
+                match self.keymap.action(KeyContext::DeletionConfirmation, key) {
+                    Some(Action::Confirm) => {
+                        let id = *id;
+                        self.main_list.remove_node(*other);
+                        if let Err(e) = self.lessons.merge_nodes(id, *other) {
+                            self.error = Some(e);
+                        }
+                        self.state = AppState::BrowsingLessons;
+                        self.refresh_main_list();
+                        self.main_list.select(id);
+                    }
+                    Some(Action::Cancel) => {
+                        self.state = AppState::BrowsingLessons;
+                    }
+                    _ => (),
+                }
+            }
+            AppState::SplittingLesson(id, splitter) => match splitter.handle_key(context, key) {
+                LessonSplitterAction::Terminate(Some(parts)) => {
+                    if let Err(e) = self.lessons.split_node(*id, parts) {
+                        self.error = Some(e);
+                    }
                     self.state = AppState::BrowsingLessons;
+                    self.refresh_main_list();
                 }
-                LessonEditFormAction::Terminate(None) => self.state = AppState::BrowsingLessons,
-                LessonEditFormAction::Noop => (),
+                LessonSplitterAction::Terminate(None) => self.state = AppState::BrowsingLessons,
+                LessonSplitterAction::Noop => (),
             },
-            AppState::Searching(finder) => {
-                if let FuzzyFinderAction::Terminate(id) = finder.handle_key(key) {
+            AppState::ConfirmingBulkDeletion(ids) => {
+                match self.keymap.action(KeyContext::DeletionConfirmation, key) {
+                    Some(Action::Confirm) => {
+                        for &id in ids.iter() {
+                            self.main_list.remove_node(id);
+                            self.lessons.delete_node(id).unwrap();
+                        }
+                        self.main_list.clear_marks();
+                        self.state = AppState::BrowsingLessons;
+                        self.refresh_main_list();
+                    }
+                    Some(Action::Cancel) => {
+                        self.state = AppState::BrowsingLessons;
+                    }
+                    _ => (),
+                }
+            }
+            AppState::ExploringTree(tree) => {
+                if let LessonTreeAction::Terminate(id) = tree.handle_key(context, key) {
                     self.state = AppState::BrowsingLessons;
                     if let Some(id) = id {
                         self.main_list.select(id);
                     }
                 }
             }
-            AppState::Studying(id, study_editor) => match study_editor.handle_key(key) {
-                StudyEditorAction::Terminate(Some(lesson_status)) => {
-                    let node = self.lessons.get(*id).unwrap();
-                    let name = node.lesson.name.clone();
-                    let direct_prerequisites = node.lesson.direct_prerequisites.clone();
-                    self.lessons
-                        .edit_node(
-                            *id,
-                            LessonInfo {
-                                name,
-                                direct_prerequisites,
-                                status: lesson_status,
-                                tags: vec![],
-                            },
-                        )
-                        .unwrap();
+            AppState::PlanningPath(path) => match path.handle_key(context, key) {
+                LearningPathAction::Study(id) => {
+                    let status = self.lessons.get(id).unwrap().lesson.status;
+                    let studying = AppState::Studying(id, Instant::now(), StudyEditor::new(status));
+                    if let AppState::PlanningPath(path) =
+                        std::mem::replace(&mut self.state, studying)
+                    {
+                        self.suspended_path = Some(path);
+                    }
+                    self.main_list.select(id);
+                }
+                LearningPathAction::Terminate(id) => {
                     self.state = AppState::BrowsingLessons;
+                    if let Some(id) = id {
+                        self.main_list.select(id);
+                    }
                 }
-                StudyEditorAction::Terminate(None) => self.state = AppState::BrowsingLessons,
-                StudyEditorAction::Noop => (),
+                LearningPathAction::Noop => (),
             },
-            AppState::ConfirmingDeletion(id) => match key.code {
-                KeyCode::Char('Y') => {
-                    self.main_list.remove_node(*id);
-                    self.lessons.delete_node(*id).unwrap();
+            AppState::ChoosingSort(selector) => match selector.handle_key(&self.keymap, key) {
+                SortSelectorAction::Terminate(Some(order)) => {
                     self.state = AppState::BrowsingLessons;
+                    self.session.sort_order = order;
+                    if let Err(e) = self.session.save() {
+                        warn!("could not save the session: {e}");
+                    }
+                    self.refresh_main_list();
                 }
-                KeyCode::Char('n') | KeyCode::Esc => {
+                SortSelectorAction::Terminate(None) => self.state = AppState::BrowsingLessons,
+                SortSelectorAction::Noop => (),
+            },
+            AppState::PromptingReview(prompt) => {
+                if let ReviewPromptAction::Terminate(ids) = prompt.handle_key(context, key) {
+                    for id in ids {
+                        self.lessons.review_early(id).unwrap();
+                    }
+                    self.state = self.state_after_studying();
+                    self.refresh_main_list();
+                }
+            }
+            AppState::ShowingStatistics(statistics) => {
+                if let StatisticsScreenAction::Terminate = statistics.handle_key(&self.keymap, key)
+                {
                     self.state = AppState::BrowsingLessons;
                 }
-                _ => (),
-            },
+            }
+            AppState::Studying(_, _, study_editor) => {
+                let action = study_editor.handle_key(&self.keymap, key);
+                self.handle_study_editor_action(action);
+            }
+            AppState::ConfirmingDeletion(id) => {
+                match self.keymap.action(KeyContext::DeletionConfirmation, key) {
+                    Some(Action::Confirm) => {
+                        self.main_list.remove_node(*id);
+                        self.lessons.delete_node(*id).unwrap();
+                        self.state = AppState::BrowsingLessons;
+                        self.refresh_main_list();
+                    }
+                    Some(Action::DeleteReconnecting) => {
+                        self.main_list.remove_node(*id);
+                        if let Err(e) = self.lessons.delete_node_reconnecting(*id) {
+                            self.error = Some(e);
+                        }
+                        self.state = AppState::BrowsingLessons;
+                        self.refresh_main_list();
+                    }
+                    Some(Action::Cancel) => {
+                        self.state = AppState::BrowsingLessons;
+                    }
+                    _ => (),
+                }
+            }
             AppState::Quitting => (),
         }
     }
 
+    /// The key contexts keys are handled in, in the current state, most specific first.
+    fn key_contexts(&self) -> Vec<KeyContext> {
+        let mut contexts = match &self.state {
+            AppState::BrowsingLessons => vec![KeyContext::Browsing, KeyContext::List],
+            AppState::AddingNewLesson(form) | AppState::EditingLesson(_, form) => {
+                form.key_contexts()
+            }
+            AppState::Searching(finder)
+            | AppState::ChoosingBulkPrerequisite(finder)
+            | AppState::ChoosingMergedLesson(_, finder) => finder.key_contexts(),
+            AppState::SplittingLesson(_, splitter) => splitter.key_contexts(),
+            AppState::ConfirmingDeletion(_)
+            | AppState::ConfirmingBulkDeletion(_)
+            | AppState::ConfirmingMerge(_, _) => vec![KeyContext::DeletionConfirmation],
+            AppState::Studying(_, _, _) => vec![KeyContext::StudyEditor],
+            AppState::ExploringTree(_) => vec![KeyContext::LessonTree, KeyContext::List],
+            AppState::PlanningPath(_) => vec![KeyContext::LearningPath, KeyContext::List],
+            AppState::ChoosingSort(_) => vec![KeyContext::SortSelector, KeyContext::List],
+            AppState::ShowingStatistics(_) => vec![KeyContext::Statistics],
+            AppState::PromptingReview(_) => vec![KeyContext::ReviewPrompt, KeyContext::List],
+            AppState::ChoosingBulkAction(_) => vec![KeyContext::BulkActions, KeyContext::List],
+            AppState::EnteringBulkTag(_, _) => vec![KeyContext::TagPrompt, KeyContext::TextInput],
+            AppState::Quitting => vec![],
+        };
+        contexts.push(KeyContext::Help);
+        contexts
+    }
+
+    /// Handle a mouse event. Only the main list, the side panel, the lesson forms, the search and
+    /// the study editor can be used with the mouse.
+    fn handle_mouse(&mut self, mouse: &MouseEvent) {
+        match &mut self.state {
+            AppState::BrowsingLessons => {
+                let on_list = self.main_list.handle_mouse(mouse);
+                if !on_list {
+                    self.handle_mouse_side_panel(mouse);
+                }
+            }
+            AppState::AddingNewLesson(form) | AppState::EditingLesson(_, form) => {
+                let action = form.handle_mouse(mouse);
+                self.handle_lesson_form_action(action);
+            }
+            AppState::Searching(finder) => {
+                let action = finder.handle_mouse(mouse);
+                self.handle_search_action(action);
+            }
+            AppState::Studying(_, _, study_editor) => {
+                let action = study_editor.handle_mouse(mouse);
+                self.handle_study_editor_action(action);
+            }
+            _ => (),
+        }
+    }
+
+    /// Insert pasted text in the text field being edited, if any.
+    fn handle_paste(&mut self, text: &str) {
+        let context = Context {
+            lessons: self.lessons.lessons(),
+            children: self.lessons.children(),
+            keymap: &self.keymap,
+            theme: &self.theme,
+        };
+        match &mut self.state {
+            AppState::AddingNewLesson(form) | AppState::EditingLesson(_, form) => {
+                form.handle_paste(context, text);
+            }
+            AppState::Searching(finder)
+            | AppState::ChoosingBulkPrerequisite(finder)
+            | AppState::ChoosingMergedLesson(_, finder) => finder.handle_paste(context, text),
+            AppState::EnteringBulkTag(_, prompt) => prompt.handle_paste(text),
+            AppState::SplittingLesson(_, splitter) => splitter.handle_paste(text),
+            _ => (),
+        }
+    }
+
+    /// Select the prerequisite clicked in the side panel, if any.
+    fn handle_mouse_side_panel(&mut self, mouse: &MouseEvent) {
+        let area = self.side_panel_prerequisites.get();
+        if mouse.kind != MouseEventKind::Down(MouseButton::Left)
+            || !area.contains(Position::new(mouse.column, mouse.row))
+        {
+            return;
+        }
+        let Some(id) = self.main_list.currently_selected_id() else {
+            return;
+        };
+        let index = usize::from(mouse.row - area.y);
+        if let Some(&prerequisite) = self
+            .lessons
+            .get(id)
+            .unwrap()
+            .lesson
+            .direct_prerequisites
+            .get(index)
+        {
+            self.main_list.select(prerequisite);
+        }
+    }
+
+    /// Save the lesson being added or edited once its form is closed.
+    fn handle_lesson_form_action(&mut self, action: LessonEditFormAction) {
+        let LessonEditFormAction::Terminate(lesson_info) = action else {
+            return;
+        };
+        let state = std::mem::replace(&mut self.state, AppState::BrowsingLessons);
+        let Some(lesson_info) = lesson_info else {
+            return;
+        };
+        match state {
+            AppState::AddingNewLesson(_) => {
+                let id = self.lessons.create_new_node(lesson_info).unwrap();
+                self.refresh_main_list();
+                self.main_list.select(id);
+            }
+            AppState::EditingLesson(id, _) => {
+                self.lessons.edit_node(id, lesson_info).unwrap();
+                self.refresh_main_list();
+            }
+            _ => (),
+        }
+    }
+
+    fn handle_search_action(&mut self, action: FuzzyFinderAction) {
+        match action {
+            FuzzyFinderAction::Terminate(id) => {
+                self.state = AppState::BrowsingLessons;
+                if let Some(id) = id {
+                    self.main_list.select(id);
+                }
+            }
+            FuzzyFinderAction::MarkAll(ids) => {
+                self.state = AppState::BrowsingLessons;
+                self.main_list.mark(ids);
+            }
+            FuzzyFinderAction::Noop => (),
+        }
+    }
+
+    /// Record the study session once the study editor is closed.
+    fn handle_study_editor_action(&mut self, action: StudyEditorAction) {
+        let AppState::Studying(id, started, _) = &self.state else {
+            return;
+        };
+        let id = *id;
+        match action {
+            StudyEditorAction::Terminate(Some((lesson_status, grade))) => {
+                let duration = started.elapsed();
+                let old_status = self.lessons.get(id).unwrap().lesson.status;
+                self.lessons
+                    .study(id, lesson_status, grade, Some(duration))
+                    .unwrap();
+                self.refresh_main_list();
+
+                // the learning path stays suspended until the prompt is closed
+                if self.review_prerequisites_of_failed
+                    && grade::is_lapse(&old_status, &lesson_status, grade)
+                {
+                    let candidates = self.lessons.early_review_candidates(id).unwrap();
+                    if !candidates.is_empty() {
+                        self.state = AppState::PromptingReview(ReviewPrompt::new(id, candidates));
+                        return;
+                    }
+                }
+                self.state = self.state_after_studying();
+            }
+            StudyEditorAction::Terminate(None) => self.state = self.state_after_studying(),
+            StudyEditorAction::Noop => (),
+        }
+    }
+
+    /// The state to go back to once a study session is over, including the prompt to review the
+    /// prerequisites of a failed lesson: the learning path it was started from, if any.
+    fn state_after_studying(&mut self) -> AppState {
+        match self.suspended_path.take() {
+            Some(path) => AppState::PlanningPath(path),
+            None => AppState::BrowsingLessons,
+        }
+    }
+
+    /// The lessons bulk actions apply to: the marked ones, or else the selected one.
+    fn bulk_targets(&self) -> Vec<Id> {
+        let marked = self.main_list.marked_ids();
+        if marked.is_empty() {
+            self.main_list.currently_selected_id().into_iter().collect()
+        } else {
+            marked
+        }
+    }
+
+    /// Apply `edit` to every lesson of `ids`. It returns whether it changed the lesson, so that
+    /// only the lessons that changed are saved.
+    fn edit_lessons(&mut self, ids: &[Id], edit: impl Fn(&mut LessonInfo) -> bool) {
+        for &id in ids {
+            let mut lesson = self.lessons.get(id).unwrap().lesson.clone();
+            if edit(&mut lesson) {
+                self.lessons.edit_node(id, lesson).unwrap();
+            }
+        }
+    }
+
+    /// Do `action` to the marked lessons, or first ask what is needed to do it.
+    fn start_bulk_action(&mut self, action: BulkAction) {
+        let targets = self.bulk_targets();
+        self.state = AppState::BrowsingLessons;
+        match action {
+            BulkAction::AddTag => {
+                self.state = AppState::EnteringBulkTag(action, TagPrompt::new("Tag to add"));
+            }
+            BulkAction::RemoveTag => {
+                self.state = AppState::EnteringBulkTag(action, TagPrompt::new("Tag to remove"));
+            }
+            BulkAction::SetKnown | BulkAction::SetNeverStudied => {
+                let status = match action {
+                    BulkAction::SetKnown => LessonStatus::GoodEnough,
+                    _ => LessonStatus::NotPracticed,
+                };
+                self.edit_lessons(&targets, |lesson| {
+                    // both statuses have no data, so telling variants apart is enough
+                    let changed =
+                        std::mem::discriminant(&lesson.status) != std::mem::discriminant(&status);
+                    lesson.status = status;
+                    changed
+                });
+                self.refresh_main_list();
+            }
+            BulkAction::AddPrerequisite => {
+                // a lesson depending on one of the targets would create a cycle
+                let candidates = self
+                    .lessons
+                    .get_ids()
+                    .into_iter()
+                    .filter(|&id| {
+                        targets
+                            .iter()
+                            .all(|&target| !self.lessons.depends_on(id, target).unwrap())
+                    })
+                    .collect();
+                self.state = AppState::ChoosingBulkPrerequisite(FuzzyFinder::new(
+                    self.get_context(),
+                    candidates,
+                ));
+            }
+            BulkAction::Delete => self.state = AppState::ConfirmingBulkDeletion(targets),
+        }
+    }
+
     fn handle_key_browsing(&mut self, key: &KeyEvent) {
-        match key.code {
-            KeyCode::Char('q') => self.state = AppState::Quitting,
-            KeyCode::Char('a') => {
+        match self.keymap.action(KeyContext::Browsing, key) {
+            Some(Action::Quit) => self.state = AppState::Quitting,
+            Some(Action::AddLesson) => {
                 self.state = AppState::AddingNewLesson(LessonEditForm::new(
                     self.lessons
                         .lessons()
@@ -541,21 +1382,25 @@ impl App {
                     LessonInfo::default(),
                 ))
             }
-            KeyCode::Char('/') => {
+            Some(Action::Search) => {
                 self.state = AppState::Searching(FuzzyFinder::new(
-                    self.lessons
-                        .lessons()
-                        .iter()
-                        .map(|(id, node)| (*id, node.lesson.clone()))
-                        .collect(),
+                    self.get_context(),
+                    self.lessons.get_ids(),
                 ))
             }
-            KeyCode::Char('d') => {
+            Some(Action::ShowLeeches) => {
+                self.state = AppState::Searching(FuzzyFinder::with_query(
+                    self.get_context(),
+                    self.lessons.get_ids(),
+                    &format!("lapses>={} ", self.leech_threshold),
+                ))
+            }
+            Some(Action::DeleteLesson) => {
                 if let Some(id) = self.main_list.currently_selected_id() {
                     self.state = AppState::ConfirmingDeletion(id);
                 }
             }
-            KeyCode::Char('e') => {
+            Some(Action::EditLesson) => {
                 if let Some(currently_selected) = self.main_list.currently_selected_id() {
                     let form = LessonEditForm::new(
                         self.lessons
@@ -571,7 +1416,7 @@ impl App {
                     self.state = AppState::EditingLesson(currently_selected, form);
                 }
             }
-            KeyCode::Char('l') => {
+            Some(Action::StudyLesson) => {
                 if let Some(currently_selected_id) = self.main_list.currently_selected_id() {
                     let status = self
                         .lessons
@@ -581,16 +1426,149 @@ impl App {
                         .lesson
                         .status;
 
+                    self.state = AppState::Studying(
+                        currently_selected_id,
+                        Instant::now(),
+                        StudyEditor::new(status),
+                    );
+                }
+            }
+            Some(Action::OpenTree) => {
+                if let Some(id) = self.main_list.currently_selected_id() {
+                    self.state = AppState::ExploringTree(LessonTree::new(self.get_context(), id));
+                }
+            }
+            Some(Action::ChooseSort) => {
+                self.state = AppState::ChoosingSort(SortSelector::new(self.session.sort_order));
+            }
+            Some(Action::ShowStatistics) => {
+                let today = chrono::offset::Local::now().date_naive();
+                self.state = AppState::ShowingStatistics(StatisticsScreen::new(
+                    self.lessons.statistics(today),
+                ));
+            }
+            Some(Action::PlanPath) => {
+                if let Some(id) = self.main_list.currently_selected_id() {
+                    let path = self.lessons.learning_path(id).unwrap();
                     self.state =
-                        AppState::Studying(currently_selected_id, StudyEditor::new(status));
+                        AppState::PlanningPath(LearningPath::new(id, path, self.lessons_per_day));
                 }
             }
-            KeyCode::Char('r') => {
+            Some(Action::RecommendNext) => {
+                let today = chrono::offset::Local::now().date_naive();
+                if let Some(id) = self.lessons.recommend_next(today) {
+                    self.main_list.select(id);
+                }
+            }
+            Some(action @ (Action::RaisePriority | Action::LowerPriority)) => {
+                if let Some(id) = self.main_list.currently_selected_id() {
+                    let lesson = &self.lessons.get(id).unwrap().lesson;
+                    let priority = match action {
+                        Action::RaisePriority => lesson.priority.raised(),
+                        _ => lesson.priority.lowered(),
+                    };
+                    let lesson = LessonInfo {
+                        priority,
+                        ..lesson.clone()
+                    };
+                    self.lessons.edit_node(id, lesson).unwrap();
+                }
+            }
+            Some(Action::RandomPending) => {
                 if let Some(id) = self.lessons.random_pending(&mut self.rng) {
                     self.main_list.select(id);
                 }
             }
-            _ => self.main_list.handle_key(key),
+            Some(Action::ToggleMark) => {
+                self.main_list.toggle_mark();
+                self.main_list
+                    .list_state_refcell()
+                    .borrow_mut()
+                    .select_next();
+            }
+            Some(Action::VisualSelect) => self.main_list.toggle_visual_selection(),
+            Some(Action::ClearMarks) => self.main_list.clear_marks(),
+            Some(Action::MergeLesson) => {
+                if let Some(id) = self.main_list.currently_selected_id() {
+                    let candidates = self
+                        .lessons
+                        .get_ids()
+                        .into_iter()
+                        .filter(|&other| self.lessons.can_merge(id, other).unwrap())
+                        .collect();
+                    self.state = AppState::ChoosingMergedLesson(
+                        id,
+                        FuzzyFinder::new(self.get_context(), candidates),
+                    );
+                }
+            }
+            Some(Action::SplitLesson) => {
+                if let Some(id) = self.main_list.currently_selected_id() {
+                    self.state =
+                        AppState::SplittingLesson(id, LessonSplitter::new(self.get_context(), id));
+                }
+            }
+            Some(Action::OpenBulkActions) => {
+                self.main_list.end_visual_selection();
+                let num_targets = self.bulk_targets().len();
+                if num_targets > 0 {
+                    self.state = AppState::ChoosingBulkAction(BulkActionMenu::new(num_targets));
+                }
+            }
+            _ => self.main_list.handle_key(&self.keymap, key),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use chrono::Days;
+    use crossterm::event::{KeyCode, KeyModifiers};
+
+    use super::*;
+
+    #[test]
+    fn test_lapse_during_learning_path() {
+        let storage = Storage::Database(SQLiteBackend::open(Path::new(":memory:")).unwrap());
+        let mut lessons = Graph::get_from_database(storage).unwrap();
+
+        let today = chrono::offset::Local::now().date_naive();
+        let practiced = LessonStatus::Practiced {
+            level: 3,
+            last_practiced: today - Days::new(10),
+            good_until: today + Days::new(5),
+        };
+        let prerequisite = lessons
+            .create_new_node(LessonInfo {
+                name: String::from("prerequisite"),
+                status: practiced,
+                ..LessonInfo::default()
+            })
+            .unwrap();
+        let target = lessons
+            .create_new_node(LessonInfo {
+                name: String::from("target"),
+                direct_prerequisites: vec![prerequisite],
+                status: practiced,
+                ..LessonInfo::default()
+            })
+            .unwrap();
+
+        let mut app = App::with_lessons(lessons, Config::default(), Session::default()).unwrap();
+        app.suspended_path = Some(LearningPath::new(target, vec![prerequisite, target], 3));
+        app.state = AppState::Studying(target, Instant::now(), StudyEditor::new(practiced));
+
+        let failed = practiced.new_status_if_graded(grade::Grade::Again, &mut thread_rng());
+        app.handle_study_editor_action(StudyEditorAction::Terminate(Some((
+            failed,
+            Some(grade::Grade::Again),
+        ))));
+        assert!(matches!(app.state, AppState::PromptingReview(_)));
+
+        app.handle_key(&KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE));
+        assert!(matches!(app.state, AppState::PlanningPath(_)));
+        assert!(app.suspended_path.is_none());
+    }
+}
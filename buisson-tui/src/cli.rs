@@ -0,0 +1,220 @@
+use std::path::Path;
+
+use buisson_common::{calendar::CalendarEvents, sync, IOBackend, NodeStatus};
+use buisson_database::{DirectoryBackend, SQLiteBackend};
+
+use crate::{
+    app::{load_config, open_database, open_lessons, AppError},
+    storage::StorageError,
+};
+
+const USAGE: &str = "Usage: buisson-tui [COMMAND]
+
+Without a command, the interactive interface is started.
+
+Commands:
+    search <QUERY>                      print the lessons matching QUERY, such as
+                                        'tag:math requires:\"Linear algebra\"'. QUERY
+                                        is a single argument, quoted as a whole
+    export-ics [--per-lesson] [FILE]    write the upcoming reviews to FILE as an iCalendar
+                                        file, one event per day or per lesson. Without
+                                        FILE, they are printed instead
+    sync <DATABASE>                     merge the lessons and reviews of DATABASE, a copy of
+                                        the database edited elsewhere, with this one. Both
+                                        databases hold the result afterwards
+    export-lessons <DIRECTORY>          copy the lessons and reviews of the database to
+                                        DIRECTORY, one file per lesson, to be used as
+                                        `lessons_directory` in the configuration
+    clean-prerequisites [--dry-run]     remove the prerequisites that lessons already
+                                        require through their other prerequisites. With
+                                        --dry-run, they are only listed
+    help                                print this message";
+
+/// Run the command described by `args`, the arguments of the program without its name, instead
+/// of the interactive interface.
+pub fn run(args: &[String]) -> Result<(), AppError> {
+    match args.first().map(String::as_str) {
+        // the shell has already removed the quotes of the query, so its words can't be joined
+        // back together
+        Some("search") if args.len() == 2 => search(&args[1]),
+        Some("export-ics") => {
+            let per_lesson = args[1..].iter().any(|arg| arg == "--per-lesson");
+            let files = args[1..]
+                .iter()
+                .filter(|arg| *arg != "--per-lesson")
+                .collect::<Vec<_>>();
+            if files.len() > 1 {
+                eprintln!("{USAGE}");
+                return Err(AppError::InvalidCommand(args.join(" ")));
+            }
+            let events = if per_lesson {
+                CalendarEvents::PerLesson
+            } else {
+                CalendarEvents::PerDay
+            };
+            export_ics(events, files.first().map(|file| file.as_str()))
+        }
+        Some("sync") if args.len() == 2 => sync(Path::new(&args[1])),
+        Some("export-lessons") if args.len() == 2 => export_lessons(Path::new(&args[1])),
+        Some("clean-prerequisites") if args.len() == 1 => clean_prerequisites(false),
+        Some("clean-prerequisites") if args.len() == 2 && args[1] == "--dry-run" => {
+            clean_prerequisites(true)
+        }
+        Some("help" | "--help" | "-h") => {
+            println!("{USAGE}");
+            Ok(())
+        }
+        _ => {
+            eprintln!("{USAGE}");
+            Err(AppError::InvalidCommand(args.join(" ")))
+        }
+    }
+}
+
+fn status_label(status: &NodeStatus) -> &'static str {
+    match status {
+        NodeStatus::Ok => "ok",
+        NodeStatus::Pending => "pending",
+        NodeStatus::MissingPrereq(_) => "missing",
+    }
+}
+
+fn search(request: &str) -> Result<(), AppError> {
+    let lessons = open_lessons(&load_config()?)?;
+    let results = lessons
+        .perform_search(request)
+        .map_err(AppError::InvalidQuery)?;
+
+    for (id, _) in results {
+        let node = lessons.get(id).unwrap();
+        println!("{:<8} {}", status_label(&node.status), node.lesson.name);
+    }
+    Ok(())
+}
+
+fn export_ics(events: CalendarEvents, file: Option<&str>) -> Result<(), AppError> {
+    let lessons = open_lessons(&load_config()?)?;
+    let today = chrono::offset::Local::now().date_naive();
+    let stamp = chrono::offset::Utc::now().naive_utc();
+    let calendar = lessons.to_icalendar(today, stamp, events);
+
+    match file {
+        Some(file) => std::fs::write(file, calendar).map_err(AppError::IOError),
+        None => {
+            print!("{calendar}");
+            Ok(())
+        }
+    }
+}
+
+fn sync(other_path: &Path) -> Result<(), AppError> {
+    if load_config()?.lessons_directory.is_some() {
+        eprintln!("Lessons stored in a directory are not synced, merge them with git instead");
+        return Err(AppError::InvalidCommand(format!(
+            "sync {}",
+            other_path.display()
+        )));
+    }
+    // opening a database that does not exist would create an empty one
+    if !other_path.is_file() {
+        return Err(AppError::IOError(std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            format!("{} is not a database", other_path.display()),
+        )));
+    }
+    let mut local = open_database()?;
+    let mut other = SQLiteBackend::open(other_path).map_err(AppError::SQLiteError)?;
+
+    let (merged, conflicts) = sync::merge(
+        local.sync_data().map_err(AppError::SQLiteError)?,
+        other.sync_data().map_err(AppError::SQLiteError)?,
+    );
+    let synced = chrono::offset::Utc::now().naive_utc();
+    local
+        .replace_with(&merged, synced)
+        .map_err(AppError::SQLiteError)?;
+    other
+        .replace_with(&merged, synced)
+        .map_err(AppError::SQLiteError)?;
+
+    println!(
+        "Synced with {}: {} lessons, {} reviews",
+        other_path.display(),
+        merged.lessons.len(),
+        merged.reviews.len()
+    );
+    if !conflicts.is_empty() {
+        println!("{} conflicts:", conflicts.len());
+        for conflict in conflicts {
+            println!("    {conflict}");
+        }
+    }
+    Ok(())
+}
+
+fn export_lessons(directory: &Path) -> Result<(), AppError> {
+    let database = open_database()?;
+    let target = DirectoryBackend::open(directory)
+        .map_err(|e| AppError::StorageError(StorageError::Directory(e)))?;
+
+    let target_error = |e| AppError::StorageError(StorageError::Directory(e));
+    // adding the reviews again to a previous export would duplicate them
+    if !target.query_lessons().map_err(target_error)?.is_empty()
+        || !target.query_reviews().map_err(target_error)?.is_empty()
+    {
+        eprintln!("{} already holds lessons", directory.display());
+        return Err(AppError::InvalidCommand(format!(
+            "export-lessons {}",
+            directory.display()
+        )));
+    }
+
+    let lessons = database.query_lessons().map_err(AppError::SQLiteError)?;
+    for (id, lesson) in &lessons {
+        target.add_new_lesson(*id, lesson).map_err(target_error)?;
+    }
+    let mut reviews = database.query_reviews().map_err(AppError::SQLiteError)?;
+    reviews.sort_by_key(|review| review.date);
+    for review in &reviews {
+        target.add_review(review).map_err(target_error)?;
+    }
+
+    println!(
+        "Copied {} lessons and {} reviews to {}",
+        lessons.len(),
+        reviews.len(),
+        directory.display()
+    );
+    Ok(())
+}
+
+fn clean_prerequisites(dry_run: bool) -> Result<(), AppError> {
+    let mut lessons = open_lessons(&load_config()?)?;
+    let redundant = if dry_run {
+        lessons.all_redundant_prerequisites()
+    } else {
+        lessons
+            .remove_redundant_prerequisites()
+            .map_err(AppError::LessonError)?
+    };
+
+    let name = |id| &lessons.get(id).unwrap().lesson.name;
+    let mut count = 0;
+    for (id, redundant) in &redundant {
+        for redundant in redundant {
+            println!(
+                "\"{}\" already requires \"{}\" through \"{}\"",
+                name(*id),
+                name(redundant.prerequisite),
+                name(redundant.through)
+            );
+            count += 1;
+        }
+    }
+    if dry_run {
+        println!("{count} redundant prerequisites");
+    } else {
+        println!("Removed {count} redundant prerequisites");
+    }
+    Ok(())
+}
@@ -0,0 +1,182 @@
+use std::cell::RefCell;
+
+use crossterm::event::KeyEvent;
+use ratatui::{
+    layout::{Alignment, Rect},
+    style::{Style, Stylize},
+    text::Line,
+    widgets::{Block, Borders, List, ListState},
+    Frame,
+};
+
+use crate::{
+    components::textinput::{TextInput, TextInputStyle},
+    keymap::{Action, KeyContext, Keymap},
+};
+
+/// Something that can be done to several lessons at once.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BulkAction {
+    AddTag,
+    RemoveTag,
+    SetKnown,
+    SetNeverStudied,
+    AddPrerequisite,
+    Delete,
+}
+
+impl BulkAction {
+    pub const ALL: [BulkAction; 6] = [
+        BulkAction::AddTag,
+        BulkAction::RemoveTag,
+        BulkAction::SetKnown,
+        BulkAction::SetNeverStudied,
+        BulkAction::AddPrerequisite,
+        BulkAction::Delete,
+    ];
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            BulkAction::AddTag => "Add a tag",
+            BulkAction::RemoveTag => "Remove a tag",
+            BulkAction::SetKnown => "Set as known (Good Enough)",
+            BulkAction::SetNeverStudied => "Set as never studied",
+            BulkAction::AddPrerequisite => "Add a shared prerequisite",
+            BulkAction::Delete => "Delete",
+        }
+    }
+}
+
+/// A popup to choose what to do with the marked lessons.
+#[derive(Debug)]
+pub struct BulkActionMenu {
+    /// how many lessons the action applies to
+    num_lessons: usize,
+    list_state: RefCell<ListState>,
+}
+
+pub enum BulkActionMenuAction {
+    Noop,
+    /// The menu should be closed, and the user chose either nothing (`None`) or the given action.
+    Terminate(Option<BulkAction>),
+}
+
+impl BulkActionMenu {
+    pub fn new(num_lessons: usize) -> Self {
+        Self {
+            num_lessons,
+            list_state: RefCell::new(ListState::default().with_selected(Some(0))),
+        }
+    }
+
+    fn selected(&self) -> Option<BulkAction> {
+        self.list_state
+            .borrow()
+            .selected()
+            .and_then(|index| BulkAction::ALL.get(index).copied())
+    }
+
+    pub fn handle_key(&mut self, keymap: &Keymap, key: &KeyEvent) -> BulkActionMenuAction {
+        match keymap.action(KeyContext::BulkActions, key) {
+            Some(Action::Confirm) => return BulkActionMenuAction::Terminate(self.selected()),
+            Some(Action::Cancel) => return BulkActionMenuAction::Terminate(None),
+            _ => (),
+        }
+        match keymap.action(KeyContext::List, key) {
+            Some(Action::SelectNext) => self.list_state.get_mut().select_next(),
+            Some(Action::SelectPrevious) => self.list_state.get_mut().select_previous(),
+            _ => (),
+        }
+        BulkActionMenuAction::Noop
+    }
+
+    fn title(&self) -> String {
+        match self.num_lessons {
+            1 => String::from("1 lesson"),
+            n => format!("{n} lessons"),
+        }
+    }
+
+    /// The size the popup needs, as (width, height).
+    pub fn size(&self) -> (u16, u16) {
+        let width = BulkAction::ALL
+            .iter()
+            .map(|action| action.description().len())
+            .chain([self.title().len()])
+            .max()
+            .unwrap_or_default();
+        (width as u16 + 4, BulkAction::ALL.len() as u16 + 2)
+    }
+
+    pub fn render(&self, area: Rect, frame: &mut Frame<'_>) {
+        let block = Block::new()
+            .title(Line::from(self.title()).alignment(Alignment::Center))
+            .borders(Borders::ALL)
+            .border_style(Style::default().bold());
+
+        let list = List::new(BulkAction::ALL.iter().map(|action| action.description()))
+            .block(block)
+            .highlight_style(Style::default().reversed());
+
+        frame.render_stateful_widget(list, area, &mut self.list_state.borrow_mut());
+    }
+}
+
+/// A prompt asking for the tag to add to or remove from the marked lessons.
+#[derive(Debug)]
+pub struct TagPrompt {
+    title: &'static str,
+    input: TextInput,
+}
+
+pub enum TagPromptAction {
+    Noop,
+    /// The prompt should be closed, and the user entered either nothing (`None`) or the given
+    /// tag.
+    Terminate(Option<String>),
+}
+
+impl TagPrompt {
+    pub fn new(title: &'static str) -> Self {
+        Self {
+            title,
+            input: TextInput::default(),
+        }
+    }
+
+    pub fn handle_key(&mut self, keymap: &Keymap, key: &KeyEvent) -> TagPromptAction {
+        match keymap.action(KeyContext::TagPrompt, key) {
+            Some(Action::Confirm) => {
+                let tag = self.input.text().trim();
+                TagPromptAction::Terminate((!tag.is_empty()).then(|| String::from(tag)))
+            }
+            Some(Action::Cancel) => TagPromptAction::Terminate(None),
+            _ => {
+                self.input.handle_key(keymap, key);
+                TagPromptAction::Noop
+            }
+        }
+    }
+
+    pub fn handle_paste(&mut self, text: &str) {
+        self.input.handle_paste(text);
+    }
+
+    /// The size the popup needs, as (width, height).
+    pub fn size(&self) -> (u16, u16) {
+        (std::cmp::max(self.title.len() as u16 + 4, 40), 3)
+    }
+
+    pub fn render(&self, area: Rect, frame: &mut Frame<'_>) {
+        let block = Block::new()
+            .title(Line::from(self.title).alignment(Alignment::Center))
+            .borders(Borders::ALL)
+            .border_style(Style::default().bold());
+
+        self.input.render_with_style(
+            area,
+            frame,
+            TextInputStyle::default().block(block).display_cursor(),
+        );
+    }
+}
@@ -1,27 +1,42 @@
-use crossterm::event::{KeyCode, KeyEvent, KeyEventKind};
+use std::collections::HashMap;
+
+use crossterm::event::{KeyEvent, KeyEventKind, MouseButton, MouseEvent, MouseEventKind};
 use ratatui::{
-    layout::{Alignment, Constraint, Layout, Position, Rect},
+    layout::{Alignment, Constraint, Layout, Rect},
     style::{Style, Stylize},
     text::{Line, Span},
-    widgets::{Block, Borders, List, ListItem, Paragraph},
+    widgets::{Block, Borders, List, ListItem},
     Frame,
 };
 
-use crate::{app::Context, components::textinput::TextInput, style_from_status};
+use crate::{
+    app::Context,
+    components::textinput::{TextInput, TextInputStyle},
+    keymap::{Action, KeyContext},
+};
 
-use buisson_common::{Id, LessonInfo};
+use buisson_common::{
+    query::{Query, QueryParseError},
+    Id,
+};
 
 use super::node_list::NodeList;
 
-/// A fuzzy finder, useful to search for lessons by name.
+/// A fuzzy finder, useful to search for lessons by name. The search also accepts the filters of
+/// the query language, like `tag:math`.
 #[derive(Debug)]
 pub struct FuzzyFinder {
-    /// The original list of elements you are searching through
-    original_list: Vec<(Id, LessonInfo)>,
+    /// The ids of the lessons you are searching through
+    candidates: Vec<Id>,
     /// The component displaying the list of lesssons matching the current search
     match_list: NodeList,
     search_bar: TextInput,
     state: FuzzyFinderState,
+    /// for every lesson in `match_list`, the indices of the characters of its name that matched
+    /// the search
+    matched_chars: HashMap<Id, Vec<usize>>,
+    /// the reason why the current search could not be parsed, if it couldn't
+    error: Option<QueryParseError>,
 }
 
 #[derive(Debug)]
@@ -37,69 +52,146 @@ pub enum FuzzyFinderAction {
     /// The fuzzy finder should be terminated, and the user selected either nothing (`None`) or
     /// the lesson whose `Id` is given here.
     Terminate(Option<Id>),
+    /// The fuzzy finder should be terminated, and the lessons it found marked.
+    MarkAll(Vec<Id>),
 }
 
 impl FuzzyFinder {
-    pub fn new(original_list: Vec<(Id, LessonInfo)>) -> Self {
-        let id_list = original_list.iter().map(|&(id, _)| id).collect();
-
-        let match_list = NodeList::new(id_list);
-        Self {
-            original_list,
-            match_list,
-            search_bar: TextInput::default(),
-            state: FuzzyFinderState::TypingSearch,
-        }
+    pub fn new(context: Context<'_>, candidates: Vec<Id>) -> Self {
+        Self::with_search_bar(
+            context,
+            candidates,
+            TextInput::default(),
+            FuzzyFinderState::TypingSearch,
+        )
     }
 
-    fn perform_search(&self) -> Vec<Id> {
-        let searched_string = self.search_bar.text();
-        self.original_list
-            .iter()
-            .filter(|(_, info)| info.name.contains(searched_string))
-            .map(|&(id, _)| id)
-            .collect()
+    /// Create a fuzzy finder whose search is already filled with `query`, browsing its results.
+    pub fn with_query(context: Context<'_>, candidates: Vec<Id>, query: &str) -> Self {
+        Self::with_search_bar(
+            context,
+            candidates,
+            TextInput::new(query),
+            FuzzyFinderState::NavigatingResults,
+        )
+    }
+
+    fn with_search_bar(
+        context: Context<'_>,
+        candidates: Vec<Id>,
+        search_bar: TextInput,
+        state: FuzzyFinderState,
+    ) -> Self {
+        let mut finder = Self {
+            candidates,
+            match_list: NodeList::new(vec![]),
+            search_bar,
+            state,
+            matched_chars: HashMap::new(),
+            error: None,
+        };
+        finder.update_matches(context);
+        finder
+    }
+
+    /// Run the current search. If it is invalid, the previous results are kept.
+    fn update_matches(&mut self, context: Context<'_>) {
+        let query = match self.search_bar.text().parse::<Query>() {
+            Ok(query) => query,
+            Err(e) => {
+                self.error = Some(e);
+                return;
+            }
+        };
+        self.error = None;
+
+        let new_matches = query.search(context.lessons, self.candidates.iter().copied());
+        self.match_list
+            .change_values(new_matches.iter().map(|(id, _)| *id).collect());
+        self.matched_chars = new_matches
+            .into_iter()
+            .map(|(id, fuzzy_match)| (id, fuzzy_match.matched_chars))
+            .collect();
     }
 }
 
 impl FuzzyFinder {
-    pub fn handle_key(&mut self, key: &KeyEvent) -> FuzzyFinderAction {
+    pub fn handle_key(&mut self, context: Context<'_>, key: &KeyEvent) -> FuzzyFinderAction {
         if key.kind != KeyEventKind::Press {
             return FuzzyFinderAction::Noop;
         }
 
         match &self.state {
-            FuzzyFinderState::TypingSearch => self.handle_key_typing(key),
-            FuzzyFinderState::NavigatingResults => self.handle_key_navigating(key),
+            FuzzyFinderState::TypingSearch => self.handle_key_typing(context, key),
+            FuzzyFinderState::NavigatingResults => self.handle_key_navigating(context, key),
         }
     }
 
-    fn handle_key_typing(&mut self, key: &KeyEvent) -> FuzzyFinderAction {
-        match key.code {
-            KeyCode::Esc | KeyCode::Enter => self.state = FuzzyFinderState::NavigatingResults,
+    fn handle_key_typing(&mut self, context: Context<'_>, key: &KeyEvent) -> FuzzyFinderAction {
+        match context.keymap.action(KeyContext::SearchInput, key) {
+            Some(Action::BrowseResults) => self.state = FuzzyFinderState::NavigatingResults,
             _ => {
-                self.search_bar.handle_key(key);
-                let new_matches = self.perform_search();
-                self.match_list.change_values(new_matches);
+                self.search_bar.handle_key(context.keymap, key);
+                self.update_matches(context);
             }
         }
         FuzzyFinderAction::Noop
     }
 
-    fn handle_key_navigating(&mut self, key: &KeyEvent) -> FuzzyFinderAction {
-        match key.code {
-            KeyCode::Char('a') | KeyCode::Char('i') => {
+    fn handle_key_navigating(&mut self, context: Context<'_>, key: &KeyEvent) -> FuzzyFinderAction {
+        match context.keymap.action(KeyContext::SearchResults, key) {
+            Some(Action::EditSearch) => {
                 self.state = FuzzyFinderState::TypingSearch;
                 FuzzyFinderAction::Noop
             }
-            KeyCode::Esc => FuzzyFinderAction::Terminate(None),
-            KeyCode::Enter => FuzzyFinderAction::Terminate(self.match_list.currently_selected_id()),
+            Some(Action::Cancel) => FuzzyFinderAction::Terminate(None),
+            Some(Action::MarkResults) => FuzzyFinderAction::MarkAll(self.match_list.ids().to_vec()),
+            Some(Action::Confirm) => {
+                FuzzyFinderAction::Terminate(self.match_list.currently_selected_id())
+            }
             _ => {
-                self.match_list.handle_key(key);
+                self.match_list.handle_key(context.keymap, key);
                 FuzzyFinderAction::Noop
             }
         }
     }
+
+    /// The key contexts the finder handles keys in, in its current state, most specific first.
+    pub fn key_contexts(&self) -> Vec<KeyContext> {
+        match self.state {
+            FuzzyFinderState::TypingSearch => vec![KeyContext::SearchInput, KeyContext::TextInput],
+            FuzzyFinderState::NavigatingResults => {
+                vec![KeyContext::SearchResults, KeyContext::List]
+            }
+        }
+    }
+
+    /// Insert pasted text in the search bar, going back to editing the search if needed.
+    pub fn handle_paste(&mut self, context: Context<'_>, text: &str) {
+        self.state = FuzzyFinderState::TypingSearch;
+        self.search_bar.handle_paste(text);
+        self.update_matches(context);
+    }
+
+    /// Handle a mouse event on the results: a click selects a lesson, and a second click on it
+    /// chooses it.
+    pub fn handle_mouse(&mut self, mouse: &MouseEvent) -> FuzzyFinderAction {
+        let selected = match self.state {
+            FuzzyFinderState::TypingSearch => None,
+            FuzzyFinderState::NavigatingResults => self.match_list.currently_selected_id(),
+        };
+        if !self.match_list.handle_mouse(mouse) {
+            return FuzzyFinderAction::Noop;
+        }
+        self.state = FuzzyFinderState::NavigatingResults;
+        if mouse.kind == MouseEventKind::Down(MouseButton::Left)
+            && selected.is_some()
+            && self.match_list.currently_selected_id() == selected
+        {
+            return FuzzyFinderAction::Terminate(selected);
+        }
+        FuzzyFinderAction::Noop
+    }
 }
 
 impl FuzzyFinder {
@@ -126,24 +218,22 @@ impl FuzzyFinder {
         let list_items = self.match_list.ids().iter().map(|id| {
             let node = context.lessons.get(id).unwrap();
             let name = &node.lesson.name;
-            let occurences = name.match_indices(self.search_bar.text());
 
             let mut spans = vec![];
-            let mut prev = 0;
-
-            for (index, _) in occurences {
-                let span_not_match =
-                    Span::styled(&name[prev..index], style_from_status(&node.status));
-                spans.push(span_not_match);
-                let span_match = Span::styled(self.search_bar.text(), Style::default().blue());
-                spans.push(span_match);
-                prev = index + self.search_bar.text().len();
-            }
-
-            spans.push(Span::styled(&name[prev..], style_from_status(&node.status)));
+            spans.extend(context.theme.status_glyph(&node.status));
+            spans.extend(highlighted_name(
+                name,
+                self.matched_chars
+                    .get(id)
+                    .map(Vec::as_slice)
+                    .unwrap_or_default(),
+                context.theme.style_from_status(&node.status),
+                context.theme.search_match_style(),
+            ));
             let text = Line::from(spans);
             ListItem::new(text)
         });
+        self.match_list.set_area(block.inner(area));
         let list = List::new(list_items)
             .block(block)
             .highlight_style(Style::default().reversed());
@@ -160,8 +250,12 @@ impl FuzzyFinder {
     }
 
     fn render_searchbar(&self, area: Rect, frame: &mut Frame<'_>) {
+        let title = match &self.error {
+            None => Line::from("Search"),
+            Some(e) => Line::from(format!("Search ({e})")).red(),
+        };
         let block = Block::new()
-            .title(Line::from("Search").alignment(Alignment::Center))
+            .title(title.alignment(Alignment::Center))
             .borders(Borders::ALL)
             .border_style(if let FuzzyFinderState::TypingSearch = self.state {
                 Style::default().bold()
@@ -169,15 +263,39 @@ impl FuzzyFinder {
                 Style::default()
             });
 
-        let text_widget = Paragraph::new(self.search_bar.text()).block(block);
+        let mut style = TextInputStyle::default().block(block);
+        if matches!(self.state, FuzzyFinderState::TypingSearch) {
+            style = style.display_cursor();
+        }
+        self.search_bar.render_with_style(area, frame, style);
+    }
+}
 
-        frame.render_widget(text_widget, area);
+/// Split `name` into spans, styling the characters at indices `matched_chars` (counted in `char`s)
+/// with `match_style`, and the others with `style`.
+fn highlighted_name(
+    name: &str,
+    matched_chars: &[usize],
+    style: Style,
+    match_style: Style,
+) -> Vec<Span<'static>> {
+    let mut spans = vec![];
+    let mut current = String::new();
+    let mut current_is_match = false;
 
-        if matches!(self.state, FuzzyFinderState::TypingSearch) {
-            frame.set_cursor_position(Position {
-                x: area.x + 1 + self.search_bar.text_len(),
-                y: area.y + 1,
-            });
+    for (index, c) in name.chars().enumerate() {
+        let is_match = matched_chars.contains(&index);
+        if is_match != current_is_match && !current.is_empty() {
+            let style = if current_is_match { match_style } else { style };
+            spans.push(Span::styled(std::mem::take(&mut current), style));
         }
+        current_is_match = is_match;
+        current.push(c);
+    }
+
+    if !current.is_empty() {
+        let style = if current_is_match { match_style } else { style };
+        spans.push(Span::styled(current, style));
     }
+    spans
 }
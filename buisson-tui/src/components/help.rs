@@ -0,0 +1,121 @@
+use std::cell::Cell;
+
+use crossterm::event::KeyEvent;
+use ratatui::{
+    layout::{Alignment, Rect},
+    style::{Style, Stylize},
+    text::{Line, Span},
+    widgets::{Block, Borders, Paragraph},
+    Frame,
+};
+
+use crate::keymap::{Action, KeyContext, Keymap};
+
+/// An overlay listing every key available in the part of the interface in use, straight from the
+/// keymap.
+#[derive(Debug)]
+pub struct HelpOverlay {
+    /// the contexts whose keys are listed, most specific first
+    contexts: Vec<KeyContext>,
+    /// how many lines are scrolled past, kept from going past the end when rendering
+    scroll: Cell<u16>,
+}
+
+pub enum HelpOverlayAction {
+    Noop,
+    /// The overlay should be closed.
+    Terminate,
+}
+
+impl HelpOverlay {
+    pub fn new(contexts: Vec<KeyContext>) -> Self {
+        Self {
+            contexts,
+            scroll: Cell::default(),
+        }
+    }
+
+    pub fn handle_key(&mut self, keymap: &Keymap, key: &KeyEvent) -> HelpOverlayAction {
+        match keymap.action(KeyContext::Help, key) {
+            Some(Action::ShowHelp | Action::Cancel) => return HelpOverlayAction::Terminate,
+            Some(Action::ScrollDown) => self.scroll.set(self.scroll.get().saturating_add(1)),
+            Some(Action::ScrollUp) => self.scroll.set(self.scroll.get().saturating_sub(1)),
+            _ => (),
+        }
+        HelpOverlayAction::Noop
+    }
+
+    /// One line per bound action of every context, under a title for each context.
+    fn lines(&self, keymap: &Keymap) -> Vec<Line<'static>> {
+        let keys_width = self
+            .contexts
+            .iter()
+            .flat_map(|&context| {
+                keymap
+                    .bindings(context)
+                    .iter()
+                    .map(move |(action, _)| keymap.describe_keys(context, *action))
+            })
+            .map(|keys| keys.chars().count())
+            .max()
+            .unwrap_or_default();
+
+        let mut lines = vec![];
+        for &context in &self.contexts {
+            let bindings = keymap
+                .bindings(context)
+                .iter()
+                .filter(|(_, keys)| !keys.is_empty())
+                .collect::<Vec<_>>();
+            if bindings.is_empty() {
+                continue;
+            }
+            if !lines.is_empty() {
+                lines.push(Line::default());
+            }
+            lines.push(Line::from(context.description()).bold());
+            lines.extend(bindings.into_iter().map(|(action, _)| {
+                Line::from(vec![
+                    Span::raw(format!(
+                        "  {:keys_width$}  ",
+                        keymap.describe_keys(context, *action)
+                    )),
+                    Span::raw(action.description()).dim(),
+                ])
+            }));
+        }
+        lines
+    }
+
+    /// The size the overlay needs to show every key without scrolling, as (width, height).
+    pub fn size(&self, keymap: &Keymap) -> (u16, u16) {
+        let lines = self.lines(keymap);
+        let width = lines.iter().map(Line::width).max().unwrap_or_default();
+        (width as u16 + 2, lines.len() as u16 + 2)
+    }
+
+    pub fn render(&self, keymap: &Keymap, area: Rect, frame: &mut Frame<'_>) {
+        let lines = self.lines(keymap);
+        let block = Block::new()
+            .title(Line::from("Keys").alignment(Alignment::Center))
+            .title_bottom(
+                Line::from(format!(
+                    "{} to close",
+                    keymap.describe_keys(KeyContext::Help, Action::Cancel)
+                ))
+                .alignment(Alignment::Center),
+            )
+            .borders(Borders::ALL)
+            .border_style(Style::default().bold());
+
+        // don't scroll past the last line
+        let visible = block.inner(area).height;
+        let scroll = std::cmp::min(
+            self.scroll.get(),
+            (lines.len() as u16).saturating_sub(visible),
+        );
+        self.scroll.set(scroll);
+
+        frame.render_widget(Paragraph::new(lines).block(block).scroll((scroll, 0)), area);
+    }
+}
@@ -0,0 +1,156 @@
+use crossterm::event::KeyEvent;
+use ratatui::{
+    layout::{Alignment, Constraint, Layout, Rect},
+    style::{Style, Stylize},
+    text::{Line, Span, Text},
+    widgets::{Block, Borders, List, ListItem},
+    Frame,
+};
+
+use buisson_common::{path::days_to_study, Id, NodeStatus};
+
+use crate::{
+    app::Context,
+    keymap::{Action, KeyContext},
+};
+
+use super::node_list::NodeList;
+
+/// A checklist of every lesson to study before a target lesson can be, in study order.
+#[derive(Debug)]
+pub struct LearningPath {
+    target: Id,
+    /// the lessons of the path, as computed when it was opened. They get checked as they are
+    /// studied.
+    steps: NodeList,
+    lessons_per_day: u32,
+}
+
+pub enum LearningPathAction {
+    Noop,
+    /// The user wants to study the lesson whose `Id` is given here. The checklist should be
+    /// reopened afterwards.
+    Study(Id),
+    /// The checklist should be closed, and the user selected either nothing (`None`) or the
+    /// lesson whose `Id` is given here.
+    Terminate(Option<Id>),
+}
+
+impl LearningPath {
+    pub fn new(target: Id, path: Vec<Id>, lessons_per_day: u32) -> Self {
+        Self {
+            target,
+            steps: NodeList::new(path),
+            lessons_per_day: std::cmp::max(lessons_per_day, 1),
+        }
+    }
+
+    /// how many lessons of the path still need work.
+    fn num_remaining(&self, context: &Context<'_>) -> usize {
+        self.steps
+            .ids()
+            .iter()
+            .filter(|id| context.lessons[id].status != NodeStatus::Ok)
+            .count()
+    }
+}
+
+impl LearningPath {
+    pub fn handle_key(&mut self, context: Context<'_>, key: &KeyEvent) -> LearningPathAction {
+        match context.keymap.action(KeyContext::LearningPath, key) {
+            Some(Action::IncreasePace) => self.lessons_per_day += 1,
+            Some(Action::DecreasePace) => {
+                self.lessons_per_day = std::cmp::max(self.lessons_per_day.saturating_sub(1), 1)
+            }
+            Some(Action::StudyLesson) => {
+                if let Some(id) = self.steps.currently_selected_id() {
+                    return LearningPathAction::Study(id);
+                }
+            }
+            Some(Action::Confirm) => {
+                return LearningPathAction::Terminate(self.steps.currently_selected_id())
+            }
+            Some(Action::Cancel) => return LearningPathAction::Terminate(None),
+            _ => self.steps.handle_key(context.keymap, key),
+        }
+        LearningPathAction::Noop
+    }
+}
+
+impl LearningPath {
+    pub fn render(&self, context: Context<'_>, area: Rect, frame: &mut Frame<'_>) {
+        let block = Block::new()
+            .title(format!(
+                "Path to {}",
+                context.lessons[&self.target].lesson.name
+            ))
+            .title_alignment(Alignment::Center)
+            .borders(Borders::ALL)
+            .border_style(Style::new().bold());
+
+        let inner = block.inner(area);
+        frame.render_widget(block, area);
+
+        let layout = Layout::vertical([
+            Constraint::Min(2),
+            Constraint::Percentage(100),
+            Constraint::Min(1),
+        ])
+        .split(inner);
+
+        let remaining = self.num_remaining(&context);
+        let summary = if self.steps.ids().is_empty() {
+            String::from("Nothing to study, this lesson is reachable.")
+        } else {
+            format!(
+                "{} of {} lessons left, about {} days at {} a day",
+                remaining,
+                self.steps.ids().len(),
+                days_to_study(remaining, self.lessons_per_day),
+                self.lessons_per_day
+            )
+        };
+        frame.render_widget(Text::from(summary), layout[0]);
+
+        let items = self.steps.ids().iter().map(|id| {
+            let node = &context.lessons[id];
+            let checkbox = if node.status == NodeStatus::Ok {
+                "[x] "
+            } else {
+                "[ ] "
+            };
+            let mut spans = vec![Span::raw(checkbox)];
+            spans.extend(
+                context
+                    .theme
+                    .lesson_name(&node.lesson.name, &node.status)
+                    .spans,
+            );
+            ListItem::new(Line::from(spans))
+        });
+
+        let list = List::new(items).highlight_style(Style::default().reversed());
+        frame.render_stateful_widget(
+            list,
+            layout[1],
+            &mut self.steps.list_state_refcell().borrow_mut(),
+        );
+
+        let help = format!(
+            "Type {} to study a lesson, {}/{} to change the pace, {} to go to a lesson",
+            context
+                .keymap
+                .describe_keys(KeyContext::LearningPath, Action::StudyLesson),
+            context
+                .keymap
+                .describe_keys(KeyContext::LearningPath, Action::IncreasePace),
+            context
+                .keymap
+                .describe_keys(KeyContext::LearningPath, Action::DecreasePace),
+            context
+                .keymap
+                .describe_keys(KeyContext::LearningPath, Action::Confirm),
+        );
+        frame.render_widget(Text::from(help), layout[2]);
+    }
+}
@@ -1,17 +1,22 @@
-use std::collections::HashMap;
+use std::{cell::Cell, collections::HashMap};
 
-use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use crossterm::event::{KeyEvent, MouseButton, MouseEvent, MouseEventKind};
 use ratatui::{
     layout::{Alignment, Constraint, Layout, Position, Rect},
     style::{Style, Stylize},
-    text::{Line, Text},
+    text::{Line, Span},
     widgets::{Block, Borders, Clear, List, ListItem, Paragraph},
     Frame,
 };
 
-use crate::{app::Context, components::textinput::TextInput, style_from_status};
+use crate::{
+    app::Context,
+    components::textinput::{TextInput, TextInputStyle},
+    keymap::{Action, KeyContext},
+};
 
-use buisson_common::{Id, LessonInfo, LessonStatus};
+use buisson_common::{redundant::redundant_among, Id, LessonInfo};
+use chrono::NaiveDate;
 
 use super::{
     fuzzyfinder::{FuzzyFinder, FuzzyFinderAction},
@@ -21,21 +26,37 @@ use super::{
 #[derive(Debug)]
 pub enum LessonEditFormState {
     EditingName,
+    EditingTags,
+    EditingDeadline,
     NavigatingPrereqs,
-    AddingPrereq(FuzzyFinder),
+    AddingPrereq(Box<FuzzyFinder>),
     Validating,
 }
 
+/// Where the fields of the form were last drawn, to find the one under a mouse click.
+#[derive(Debug, Clone, Copy, Default)]
+struct FormAreas {
+    name: Rect,
+    tags: Rect,
+    deadline: Rect,
+    button: Rect,
+}
+
 pub struct LessonEditForm {
     /// represents the lessons that can be added as prerequisite. It is initialized as all the
     /// existing lessons for new lessons, and all the lessons that don't depend on the edited
     /// lesson for existing lessons
     potential_prerequisites: HashMap<Id, (LessonInfo, bool)>,
     name_input: TextInput,
+    /// the tags of the lesson, separated by commas
+    tags_input: TextInput,
+    /// the deadline of the lesson if it is a goal, as YYYY-MM-DD, or nothing
+    deadline_input: TextInput,
     prerequisites: NodeList,
     state: LessonEditFormState,
-    // why do we (only) need this?
-    lesson_status: LessonStatus,
+    /// the lesson as it was before editing, which holds the fields that the form doesn't edit
+    lesson: LessonInfo,
+    areas: Cell<FormAreas>,
 }
 
 pub enum LessonEditFormAction {
@@ -51,93 +72,201 @@ impl LessonEditForm {
             .collect();
         Self {
             potential_prerequisites,
-            name_input: TextInput::new(lesson.name),
+            name_input: TextInput::new(lesson.name.as_str()),
+            tags_input: TextInput::new(lesson.tags.join(", ")),
+            deadline_input: TextInput::new(
+                lesson
+                    .deadline
+                    .map(|date| date.to_string())
+                    .unwrap_or_default(),
+            ),
             prerequisites: NodeList::new(lesson.direct_prerequisites.clone()),
             state: LessonEditFormState::EditingName,
-            lesson_status: lesson.status,
+            lesson,
+            areas: Cell::default(),
+        }
+    }
+
+    /// The deadline in the deadline field. Returns `Err` if the field is neither empty nor a date.
+    fn deadline(&self) -> Result<Option<NaiveDate>, chrono::ParseError> {
+        let text = self.deadline_input.text().trim();
+        if text.is_empty() {
+            Ok(None)
+        } else {
+            text.parse().map(Some)
         }
     }
 
+    /// The lesson as currently filled in. Invalid deadlines are ignored.
     pub fn to_lesson_info(&self) -> LessonInfo {
         LessonInfo {
             name: self.name_input.text().into(),
             direct_prerequisites: self.prerequisites.ids().into(),
-            status: self.lesson_status,
-            tags: vec![],
+            tags: self
+                .tags_input
+                .text()
+                .split(',')
+                .map(str::trim)
+                .filter(|tag| !tag.is_empty())
+                .map(String::from)
+                .collect(),
+            deadline: self.deadline().unwrap_or(self.lesson.deadline),
+            ..self.lesson.clone()
         }
     }
 }
 
 impl LessonEditForm {
-    pub fn handle_key(&mut self, key: &KeyEvent) -> LessonEditFormAction {
+    pub fn handle_key(&mut self, context: Context<'_>, key: &KeyEvent) -> LessonEditFormAction {
+        let keymap = context.keymap;
         match &mut self.state {
-            LessonEditFormState::EditingName => match key.code {
-                KeyCode::Tab | KeyCode::Enter => {
-                    self.state = LessonEditFormState::NavigatingPrereqs
-                }
-                KeyCode::Char('j') if key.modifiers == KeyModifiers::ALT => {
-                    self.state = LessonEditFormState::NavigatingPrereqs
-                }
-                KeyCode::Esc => return LessonEditFormAction::Terminate(None),
-                _ => self.name_input.handle_key(key),
+            LessonEditFormState::EditingName => match keymap.action(KeyContext::LessonName, key) {
+                Some(Action::NextField) => self.state = LessonEditFormState::EditingTags,
+                Some(Action::Cancel) => return LessonEditFormAction::Terminate(None),
+                _ => self.name_input.handle_key(keymap, key),
             },
-            LessonEditFormState::NavigatingPrereqs => match key.code {
-                KeyCode::Char('a') => {
-                    self.state = LessonEditFormState::AddingPrereq(FuzzyFinder::new(
-                        self.potential_prerequisites
-                            .clone()
-                            .into_iter()
-                            .filter(|(_, (_, already_prereq))| !already_prereq)
-                            .map(|(id, (info, _))| (id, info))
-                            .collect(),
-                    ));
-                }
-                KeyCode::Esc => return LessonEditFormAction::Terminate(None),
-                KeyCode::Tab => self.state = LessonEditFormState::Validating,
-                KeyCode::BackTab => self.state = LessonEditFormState::EditingName,
-                KeyCode::Char('j') if key.modifiers == KeyModifiers::ALT => {
-                    self.state = LessonEditFormState::Validating
-                }
-                KeyCode::Char('k') if key.modifiers == KeyModifiers::ALT => {
-                    self.state = LessonEditFormState::EditingName
-                }
-                KeyCode::Char('d') => {
-                    if let Some(id) = self.prerequisites.currently_selected_id() {
-                        self.potential_prerequisites
-                            .entry(id)
-                            .and_modify(|(_, already_prereq)| *already_prereq = false);
-                        self.prerequisites.remove_node(id);
-                    }
-                }
-                _ => self.prerequisites.handle_key(key),
+            LessonEditFormState::EditingTags => match keymap.action(KeyContext::LessonTags, key) {
+                Some(Action::NextField) => self.state = LessonEditFormState::EditingDeadline,
+                Some(Action::PreviousField) => self.state = LessonEditFormState::EditingName,
+                Some(Action::Cancel) => return LessonEditFormAction::Terminate(None),
+                _ => self.tags_input.handle_key(keymap, key),
             },
-            LessonEditFormState::AddingPrereq(finder) => match finder.handle_key(key) {
-                FuzzyFinderAction::Terminate(Some(id)) => {
-                    self.prerequisites.push(id);
-                    self.potential_prerequisites
-                        .entry(id)
-                        .and_modify(|(_, already_prereq)| *already_prereq = true);
-                    self.state = LessonEditFormState::NavigatingPrereqs;
-                }
-                FuzzyFinderAction::Terminate(None) => {
-                    self.state = LessonEditFormState::NavigatingPrereqs;
+            LessonEditFormState::EditingDeadline => {
+                match keymap.action(KeyContext::LessonDeadline, key) {
+                    Some(Action::NextField) => self.state = LessonEditFormState::NavigatingPrereqs,
+                    Some(Action::PreviousField) => self.state = LessonEditFormState::EditingTags,
+                    Some(Action::Cancel) => return LessonEditFormAction::Terminate(None),
+                    _ => self.deadline_input.handle_key(keymap, key),
                 }
-                FuzzyFinderAction::Noop => (),
-            },
-            LessonEditFormState::Validating => match key.code {
-                KeyCode::Char('k') if key.modifiers == KeyModifiers::ALT => {
-                    self.state = LessonEditFormState::NavigatingPrereqs
+            }
+            LessonEditFormState::NavigatingPrereqs => {
+                match keymap.action(KeyContext::LessonPrerequisites, key) {
+                    Some(Action::AddPrerequisite) => {
+                        self.state = LessonEditFormState::AddingPrereq(Box::new(FuzzyFinder::new(
+                            context.clone(),
+                            self.potential_prerequisites
+                                .iter()
+                                .filter(|(_, (_, already_prereq))| !already_prereq)
+                                .map(|(id, _)| *id)
+                                .collect(),
+                        )));
+                    }
+                    Some(Action::Cancel) => return LessonEditFormAction::Terminate(None),
+                    Some(Action::NextField) => self.state = LessonEditFormState::Validating,
+                    Some(Action::PreviousField) => {
+                        self.state = LessonEditFormState::EditingDeadline
+                    }
+                    Some(Action::RemovePrerequisite) => {
+                        if let Some(id) = self.prerequisites.currently_selected_id() {
+                            self.potential_prerequisites
+                                .entry(id)
+                                .and_modify(|(_, already_prereq)| *already_prereq = false);
+                            self.prerequisites.remove_node(id);
+                        }
+                    }
+                    _ => self.prerequisites.handle_key(keymap, key),
                 }
-                KeyCode::BackTab => self.state = LessonEditFormState::NavigatingPrereqs,
-                KeyCode::Enter => {
-                    return LessonEditFormAction::Terminate(Some(self.to_lesson_info()))
+            }
+            LessonEditFormState::AddingPrereq(finder) => {
+                let action = finder.handle_key(context, key);
+                self.handle_finder_action(action);
+            }
+            LessonEditFormState::Validating => {
+                match keymap.action(KeyContext::LessonValidation, key) {
+                    Some(Action::PreviousField) => {
+                        self.state = LessonEditFormState::NavigatingPrereqs
+                    }
+                    Some(Action::Confirm) => return self.validate(),
+                    Some(Action::Cancel) => return LessonEditFormAction::Terminate(None),
+                    _ => (),
                 }
-                KeyCode::Esc => return LessonEditFormAction::Terminate(None),
-                _ => (),
-            },
+            }
+        }
+        LessonEditFormAction::Noop
+    }
+
+    /// The key contexts the form handles keys in, in its current state, most specific first.
+    pub fn key_contexts(&self) -> Vec<KeyContext> {
+        match &self.state {
+            LessonEditFormState::EditingName => vec![KeyContext::LessonName, KeyContext::TextInput],
+            LessonEditFormState::EditingTags => vec![KeyContext::LessonTags, KeyContext::TextInput],
+            LessonEditFormState::EditingDeadline => {
+                vec![KeyContext::LessonDeadline, KeyContext::TextInput]
+            }
+            LessonEditFormState::NavigatingPrereqs => {
+                vec![KeyContext::LessonPrerequisites, KeyContext::List]
+            }
+            LessonEditFormState::AddingPrereq(finder) => finder.key_contexts(),
+            LessonEditFormState::Validating => vec![KeyContext::LessonValidation],
+        }
+    }
+
+    /// Insert pasted text in the focused field.
+    pub fn handle_paste(&mut self, context: Context<'_>, text: &str) {
+        match &mut self.state {
+            LessonEditFormState::EditingName => self.name_input.handle_paste(text),
+            LessonEditFormState::EditingTags => self.tags_input.handle_paste(text),
+            LessonEditFormState::EditingDeadline => self.deadline_input.handle_paste(text),
+            LessonEditFormState::AddingPrereq(finder) => finder.handle_paste(context, text),
+            LessonEditFormState::NavigatingPrereqs | LessonEditFormState::Validating => (),
+        }
+    }
+
+    /// Handle a mouse event: a click on a field focuses it, a click on a prerequisite selects it,
+    /// and a click on the button validates the form.
+    pub fn handle_mouse(&mut self, mouse: &MouseEvent) -> LessonEditFormAction {
+        if let LessonEditFormState::AddingPrereq(finder) = &mut self.state {
+            let action = finder.handle_mouse(mouse);
+            self.handle_finder_action(action);
+            return LessonEditFormAction::Noop;
+        }
+        if self.prerequisites.handle_mouse(mouse) {
+            self.state = LessonEditFormState::NavigatingPrereqs;
+            return LessonEditFormAction::Noop;
+        }
+        if mouse.kind != MouseEventKind::Down(MouseButton::Left) {
+            return LessonEditFormAction::Noop;
+        }
+
+        let position = Position::new(mouse.column, mouse.row);
+        let areas = self.areas.get();
+        if areas.name.contains(position) {
+            self.state = LessonEditFormState::EditingName;
+        } else if areas.tags.contains(position) {
+            self.state = LessonEditFormState::EditingTags;
+        } else if areas.deadline.contains(position) {
+            self.state = LessonEditFormState::EditingDeadline;
+        } else if areas.button.contains(position) {
+            return self.validate();
         }
         LessonEditFormAction::Noop
     }
+
+    fn handle_finder_action(&mut self, action: FuzzyFinderAction) {
+        match action {
+            FuzzyFinderAction::Terminate(Some(id)) => {
+                self.prerequisites.push(id);
+                self.potential_prerequisites
+                    .entry(id)
+                    .and_modify(|(_, already_prereq)| *already_prereq = true);
+                self.state = LessonEditFormState::NavigatingPrereqs;
+            }
+            FuzzyFinderAction::Terminate(None) => {
+                self.state = LessonEditFormState::NavigatingPrereqs;
+            }
+            // only one prerequisite is added at a time
+            FuzzyFinderAction::MarkAll(_) | FuzzyFinderAction::Noop => (),
+        }
+    }
+
+    /// Close the form with the lesson as filled in, or go back to the deadline if it is invalid.
+    fn validate(&mut self) -> LessonEditFormAction {
+        if self.deadline().is_ok() {
+            return LessonEditFormAction::Terminate(Some(self.to_lesson_info()));
+        }
+        self.state = LessonEditFormState::EditingDeadline;
+        LessonEditFormAction::Noop
+    }
 }
 
 impl LessonEditForm {
@@ -152,6 +281,8 @@ impl LessonEditForm {
         frame.render_widget(main_block, area);
 
         let layout = Layout::vertical([
+            Constraint::Min(3),
+            Constraint::Min(3),
             Constraint::Min(3),
             Constraint::Percentage(100),
             Constraint::Min(5),
@@ -159,14 +290,48 @@ impl LessonEditForm {
         .split(main_block_inner);
 
         let name_input_area = layout[0];
-        let prereqs_area = layout[1];
-        let validating_button_area = layout[2];
+        let tags_input_area = layout[1];
+        let deadline_input_area = layout[2];
+        let prereqs_area = layout[3];
+        let validating_button_area = layout[4];
+        let mut areas = FormAreas {
+            name: name_input_area,
+            tags: tags_input_area,
+            deadline: deadline_input_area,
+            button: Rect::default(),
+        };
 
-        self.render_name_input(name_input_area, frame);
+        self.render_text_input(
+            &self.name_input,
+            "Lesson Name",
+            matches!(self.state, LessonEditFormState::EditingName),
+            name_input_area,
+            frame,
+        );
+        self.render_text_input(
+            &self.tags_input,
+            "Tags (separated by commas)",
+            matches!(self.state, LessonEditFormState::EditingTags),
+            tags_input_area,
+            frame,
+        );
+        let deadline_title = if self.deadline().is_ok() {
+            "Goal deadline (YYYY-MM-DD, empty if not a goal)"
+        } else {
+            "Goal deadline (invalid date, expected YYYY-MM-DD)"
+        };
+        self.render_text_input(
+            &self.deadline_input,
+            deadline_title,
+            matches!(self.state, LessonEditFormState::EditingDeadline),
+            deadline_input_area,
+            frame,
+        );
 
         self.render_prereq_list(context.clone(), prereqs_area, frame);
 
-        self.render_button(validating_button_area, frame);
+        areas.button = self.render_button(validating_button_area, frame);
+        self.areas.set(areas);
 
         if let LessonEditFormState::AddingPrereq(finder) = &self.state {
             let layout = Layout::vertical([
@@ -187,35 +352,46 @@ impl LessonEditForm {
         }
     }
 
-    fn render_name_input(&self, area: Rect, frame: &mut Frame<'_>) {
-        let name_input_block = {
-            let mut block = Block::new().title("Lesson Name").borders(Borders::ALL);
-            if let LessonEditFormState::EditingName = self.state {
+    fn render_text_input(
+        &self,
+        input: &TextInput,
+        title: &str,
+        focused: bool,
+        area: Rect,
+        frame: &mut Frame<'_>,
+    ) {
+        let input_block = {
+            let mut block = Block::new().title(title).borders(Borders::ALL);
+            if focused {
                 block = block.border_style(Style::default().bold());
             }
             block
         };
 
-        let text_widget = Paragraph::new(self.name_input.text()).block(name_input_block);
-
-        frame.render_widget(text_widget, area);
-        if matches!(self.state, LessonEditFormState::EditingName) {
-            frame.set_cursor_position(Position {
-                x: area.x + 1 + self.name_input.text_len(),
-                y: area.y + 1,
-            });
+        let mut style = TextInputStyle::default().block(input_block);
+        if focused {
+            style = style.display_cursor();
         }
+        input.render_with_style(area, frame, style);
     }
 
     fn render_prereq_list(&self, context: Context, area: Rect, frame: &mut Frame<'_>) {
         let title_style = match self.state {
-            LessonEditFormState::EditingName | LessonEditFormState::Validating => Style::default(),
+            LessonEditFormState::EditingName
+            | LessonEditFormState::EditingTags
+            | LessonEditFormState::EditingDeadline
+            | LessonEditFormState::Validating => Style::default(),
             LessonEditFormState::NavigatingPrereqs | LessonEditFormState::AddingPrereq(_) => {
                 Style::default().bold()
             }
         };
         let prereq = Line::from("Prerequisites").style(title_style);
-        let help = Line::from("Type 'a' to add a prerequisite");
+        let help = Line::from(format!(
+            "Type {} to add a prerequisite",
+            context
+                .keymap
+                .describe_keys(KeyContext::LessonPrerequisites, Action::AddPrerequisite)
+        ));
 
         let layout = Layout::vertical([
             Constraint::Min(1),
@@ -226,13 +402,24 @@ impl LessonEditForm {
 
         frame.render_widget(prereq, layout[0]);
 
+        let redundant = redundant_among(context.lessons, self.prerequisites.ids());
         let items = self.prerequisites.ids().iter().map(|id| {
             let node = context.lessons.get(id).unwrap();
-            let text = Text::from(node.lesson.name.as_str()).style(style_from_status(&node.status));
-            ListItem::from(text)
+            let mut line = context.theme.lesson_name(&node.lesson.name, &node.status);
+            if let Some(redundant) = redundant.iter().find(|r| r.prerequisite == *id) {
+                line.spans.push(
+                    Span::raw(format!(
+                        " (already required by {})",
+                        context.lessons[&redundant.through].lesson.name
+                    ))
+                    .italic(),
+                );
+            }
+            ListItem::from(line)
         });
 
         let list_widget = List::new(items).highlight_style(Style::default().reversed());
+        self.prerequisites.set_area(layout[1]);
 
         if matches!(self.state, LessonEditFormState::NavigatingPrereqs) {
             frame.render_stateful_widget(
@@ -249,7 +436,8 @@ impl LessonEditForm {
         }
     }
 
-    fn render_button(&self, area: Rect, frame: &mut Frame<'_>) {
+    /// Render the OK button in `area`, and return where it was drawn.
+    fn render_button(&self, area: Rect, frame: &mut Frame<'_>) -> Rect {
         let layout = Layout::horizontal([
             Constraint::Percentage(33),
             Constraint::Percentage(33),
@@ -273,5 +461,6 @@ impl LessonEditForm {
             .style(style);
 
         frame.render_widget(button_widget, button_area);
+        button_area
     }
 }
@@ -0,0 +1,283 @@
+use std::cell::RefCell;
+
+use crossterm::event::KeyEvent;
+use ratatui::{
+    layout::{Alignment, Constraint, Layout, Position, Rect},
+    style::{Style, Stylize},
+    text::{Line, Span},
+    widgets::{Block, Borders, List, ListItem, ListState, Paragraph, Wrap},
+    Frame,
+};
+
+use buisson_common::{restructure::SplitPart, Id};
+
+use crate::{
+    app::Context,
+    components::textinput::TextInput,
+    keymap::{Action, KeyContext},
+};
+
+/// A prerequisite or a dependent of the lesson being split.
+#[derive(Debug, Clone, Copy)]
+enum Edge {
+    Prerequisite(Id),
+    Dependent(Id),
+}
+
+/// A form to split a lesson into several parts, giving each of its prerequisites and dependents
+/// to one of them.
+#[derive(Debug)]
+pub struct LessonSplitter {
+    lesson: Id,
+    /// the names of the parts
+    parts: Vec<TextInput>,
+    /// the prerequisites and dependents of the lesson, with the index of the part they go to
+    edges: Vec<(Edge, usize)>,
+    /// the selected row: the parts come first, then the edges
+    list_state: RefCell<ListState>,
+    /// whether the name of the selected part is being edited
+    renaming: bool,
+}
+
+pub enum LessonSplitterAction {
+    Noop,
+    /// The form should be closed, and the user chose either not to split the lesson (`None`),
+    /// or to split it into the given parts.
+    Terminate(Option<Vec<SplitPart>>),
+}
+
+impl LessonSplitter {
+    /// Create a form to split `lesson` in two, every prerequisite and dependent going to the
+    /// first part.
+    pub fn new(context: Context<'_>, lesson: Id) -> Self {
+        let name = &context.lessons[&lesson].lesson.name;
+        let edges = context.lessons[&lesson]
+            .lesson
+            .direct_prerequisites
+            .iter()
+            .map(|&id| (Edge::Prerequisite(id), 0))
+            .chain(
+                context.children[&lesson]
+                    .iter()
+                    .map(|&id| (Edge::Dependent(id), 0)),
+            )
+            .collect();
+        Self {
+            lesson,
+            parts: vec![
+                TextInput::new(format!("{name} (1)")),
+                TextInput::new(format!("{name} (2)")),
+            ],
+            edges,
+            list_state: RefCell::new(ListState::default().with_selected(Some(0))),
+            renaming: false,
+        }
+    }
+
+    /// The index of the selected part, if a part is selected.
+    fn selected_part(&self) -> Option<usize> {
+        self.list_state
+            .borrow()
+            .selected()
+            .filter(|&index| index < self.parts.len())
+    }
+
+    /// The index in `edges` of the selected edge, if an edge is selected.
+    fn selected_edge(&self) -> Option<usize> {
+        self.list_state
+            .borrow()
+            .selected()
+            .and_then(|index| index.checked_sub(self.parts.len()))
+            .filter(|&index| index < self.edges.len())
+    }
+
+    fn remove_part(&mut self, index: usize) {
+        if self.parts.len() <= 2 {
+            return;
+        }
+        self.parts.remove(index);
+        // the lessons of the removed part go to the first one
+        for (_, part) in &mut self.edges {
+            if *part == index {
+                *part = 0;
+            } else if *part > index {
+                *part -= 1;
+            }
+        }
+        self.list_state.get_mut().select_previous();
+    }
+
+    fn split_parts(&self) -> Vec<SplitPart> {
+        let mut parts = self
+            .parts
+            .iter()
+            .map(|name| SplitPart {
+                name: name.text().trim().to_string(),
+                ..SplitPart::default()
+            })
+            .collect::<Vec<_>>();
+        for &(edge, part) in &self.edges {
+            match edge {
+                Edge::Prerequisite(id) => parts[part].prerequisites.push(id),
+                Edge::Dependent(id) => parts[part].dependents.push(id),
+            }
+        }
+        parts
+    }
+}
+
+impl LessonSplitter {
+    pub fn handle_key(&mut self, context: Context<'_>, key: &KeyEvent) -> LessonSplitterAction {
+        let keymap = context.keymap;
+        if self.renaming {
+            match keymap.action(KeyContext::SplitPartName, key) {
+                Some(Action::Confirm) => self.renaming = false,
+                _ => {
+                    if let Some(index) = self.selected_part() {
+                        self.parts[index].handle_key(keymap, key);
+                    }
+                }
+            }
+            return LessonSplitterAction::Noop;
+        }
+
+        match keymap.action(KeyContext::LessonSplitter, key) {
+            Some(Action::AddPart) => {
+                self.parts.push(TextInput::default());
+                self.list_state.get_mut().select(Some(self.parts.len() - 1));
+                self.renaming = true;
+            }
+            Some(Action::RemovePart) => {
+                if let Some(index) = self.selected_part() {
+                    self.remove_part(index);
+                }
+            }
+            Some(Action::RenamePart) => self.renaming = self.selected_part().is_some(),
+            Some(action @ (Action::PreviousPart | Action::NextPart)) => {
+                if let Some(index) = self.selected_edge() {
+                    let num_parts = self.parts.len();
+                    let part = &mut self.edges[index].1;
+                    *part = match action {
+                        Action::PreviousPart => (*part + num_parts - 1) % num_parts,
+                        _ => (*part + 1) % num_parts,
+                    };
+                }
+            }
+            Some(Action::Confirm) => {
+                if self.parts.iter().all(|name| !name.text().trim().is_empty()) {
+                    return LessonSplitterAction::Terminate(Some(self.split_parts()));
+                }
+            }
+            Some(Action::Cancel) => return LessonSplitterAction::Terminate(None),
+            _ => match keymap.action(KeyContext::List, key) {
+                Some(Action::SelectNext) => self.list_state.get_mut().select_next(),
+                Some(Action::SelectPrevious) => self.list_state.get_mut().select_previous(),
+                _ => (),
+            },
+        }
+        LessonSplitterAction::Noop
+    }
+
+    /// The key contexts the form handles keys in, in its current state, most specific first.
+    pub fn key_contexts(&self) -> Vec<KeyContext> {
+        if self.renaming {
+            vec![KeyContext::SplitPartName, KeyContext::TextInput]
+        } else {
+            vec![KeyContext::LessonSplitter, KeyContext::List]
+        }
+    }
+
+    /// Insert pasted text in the name of the selected part, if it is being edited.
+    pub fn handle_paste(&mut self, text: &str) {
+        if let Some(index) = self.selected_part().filter(|_| self.renaming) {
+            self.parts[index].handle_paste(text);
+        }
+    }
+}
+
+impl LessonSplitter {
+    fn part_label(index: usize) -> String {
+        format!("Part {}: ", index + 1)
+    }
+
+    fn help(&self, context: &Context<'_>) -> String {
+        let keys = |action| {
+            context
+                .keymap
+                .describe_keys(KeyContext::LessonSplitter, action)
+        };
+        format!(
+            "Type {} to add a part, {} to rename it, {} to remove it, {}/{} to give a lesson to \
+             another part, {} to split, {} to cancel",
+            keys(Action::AddPart),
+            keys(Action::RenamePart),
+            keys(Action::RemovePart),
+            keys(Action::PreviousPart),
+            keys(Action::NextPart),
+            keys(Action::Confirm),
+            keys(Action::Cancel),
+        )
+    }
+
+    pub fn render(&self, context: Context<'_>, area: Rect, frame: &mut Frame<'_>) {
+        let block = Block::new()
+            .title(format!(
+                "Split {}",
+                context.lessons[&self.lesson].lesson.name
+            ))
+            .title_alignment(Alignment::Center)
+            .borders(Borders::ALL)
+            .border_style(Style::new().bold());
+
+        let inner = block.inner(area);
+        frame.render_widget(block, area);
+
+        let layout =
+            Layout::vertical([Constraint::Percentage(100), Constraint::Min(3)]).split(inner);
+
+        let parts = self.parts.iter().enumerate().map(|(index, name)| {
+            ListItem::new(Line::from(vec![
+                Span::raw(Self::part_label(index)).bold(),
+                Span::raw(name.text()),
+            ]))
+        });
+        let edges = self.edges.iter().map(|&(edge, part)| {
+            let (id, kind) = match edge {
+                Edge::Prerequisite(id) => (id, "prerequisite"),
+                Edge::Dependent(id) => (id, "dependent"),
+            };
+            let node = &context.lessons[&id];
+            let mut spans = vec![Span::raw(format!("[{}] ", part + 1))];
+            spans.extend(
+                context
+                    .theme
+                    .lesson_name(&node.lesson.name, &node.status)
+                    .spans,
+            );
+            spans.push(Span::raw(format!(" ({kind})")).italic());
+            ListItem::new(Line::from(spans))
+        });
+
+        let list = List::new(parts.chain(edges)).highlight_style(Style::default().reversed());
+        let mut list_state = self.list_state.borrow_mut();
+        frame.render_stateful_widget(list, layout[0], &mut list_state);
+
+        let selected_part = list_state
+            .selected()
+            .filter(|&index| index < self.parts.len());
+        if let Some(index) = selected_part.filter(|_| self.renaming) {
+            let row = index.saturating_sub(list_state.offset()) as u16;
+            frame.set_cursor_position(Position {
+                x: layout[0].x
+                    + Self::part_label(index).len() as u16
+                    + self.parts[index].cursor_column(),
+                y: layout[0].y + row,
+            });
+        }
+
+        frame.render_widget(
+            Paragraph::new(self.help(&context)).wrap(Wrap { trim: true }),
+            layout[1],
+        );
+    }
+}
@@ -0,0 +1,222 @@
+use std::collections::HashSet;
+
+use crossterm::event::KeyEvent;
+use ratatui::{
+    layout::{Alignment, Constraint, Layout, Rect},
+    style::{Style, Stylize},
+    text::{Line, Span, Text},
+    widgets::{Block, Borders, List, ListItem},
+    Frame,
+};
+
+use buisson_common::Id;
+
+use crate::{
+    app::Context,
+    keymap::{Action, KeyContext},
+};
+
+use super::node_list::NodeList;
+
+/// Which way the tree goes from its root.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TreeDirection {
+    /// The children of a node are its prerequisites.
+    Ancestors,
+    /// The children of a node are the lessons depending on it.
+    Descendants,
+}
+
+/// A line of the tree, corresponding to one occurence of a lesson in it. A lesson can appear
+/// several times in the tree, if it can be reached through different paths, but only its first
+/// occurence is expanded: the number of paths through a graph can grow exponentially.
+#[derive(Debug)]
+struct TreeRow {
+    /// the ids of the lessons from the root to this row, both included.
+    path: Vec<Id>,
+    has_children: bool,
+    /// whether the lesson already appears above, where its children are shown
+    repeated: bool,
+}
+
+/// A browsable tree of the dependencies of a lesson.
+#[derive(Debug)]
+pub struct LessonTree {
+    root: Id,
+    direction: TreeDirection,
+    /// the rows that are collapsed, identified by their path
+    collapsed: HashSet<Vec<Id>>,
+    rows: Vec<TreeRow>,
+    /// contains the id of the lesson of every row, in order
+    row_list: NodeList,
+}
+
+pub enum LessonTreeAction {
+    Noop,
+    /// The tree should be closed, and the user selected either nothing (`None`) or the lesson
+    /// whose `Id` is given here.
+    Terminate(Option<Id>),
+}
+
+impl LessonTree {
+    pub fn new(context: Context<'_>, root: Id) -> Self {
+        let mut tree = Self {
+            root,
+            direction: TreeDirection::Ancestors,
+            collapsed: HashSet::new(),
+            rows: vec![],
+            row_list: NodeList::new(vec![]),
+        };
+        tree.update_rows(context);
+        tree
+    }
+
+    fn tree_children<'a>(&self, context: &Context<'a>, id: Id) -> &'a [Id] {
+        match self.direction {
+            TreeDirection::Ancestors => &context.lessons[&id].lesson.direct_prerequisites,
+            TreeDirection::Descendants => &context.children[&id],
+        }
+    }
+
+    /// Recompute the visible rows, after the tree was changed.
+    fn update_rows(&mut self, context: Context<'_>) {
+        let mut rows = vec![];
+        // depth first traversal, with the children pushed in reverse order so that they are
+        // popped in order
+        let mut stack = vec![vec![self.root]];
+        let mut seen = HashSet::new();
+        while let Some(path) = stack.pop() {
+            let id = *path.last().unwrap();
+            let children = self.tree_children(&context, id);
+            let repeated = !seen.insert(id);
+            if !repeated && !self.collapsed.contains(&path) {
+                for &child in children.iter().rev() {
+                    let mut child_path = path.clone();
+                    child_path.push(child);
+                    stack.push(child_path);
+                }
+            }
+            rows.push(TreeRow {
+                path,
+                has_children: !children.is_empty(),
+                repeated,
+            });
+        }
+
+        self.row_list
+            .change_values(rows.iter().map(|row| *row.path.last().unwrap()).collect());
+        self.rows = rows;
+    }
+
+    fn selected_row(&self) -> Option<&TreeRow> {
+        self.row_list
+            .list_state_refcell()
+            .borrow()
+            .selected()
+            .and_then(|index| self.rows.get(index))
+    }
+}
+
+impl LessonTree {
+    pub fn handle_key(&mut self, context: Context<'_>, key: &KeyEvent) -> LessonTreeAction {
+        match context.keymap.action(KeyContext::LessonTree, key) {
+            Some(Action::ToggleCollapse) => {
+                if let Some(path) = self
+                    .selected_row()
+                    .filter(|row| !row.repeated)
+                    .map(|row| row.path.clone())
+                {
+                    if !self.collapsed.remove(&path) {
+                        self.collapsed.insert(path);
+                    }
+                    self.update_rows(context);
+                }
+            }
+            Some(Action::ToggleTreeDirection) => {
+                self.direction = match self.direction {
+                    TreeDirection::Ancestors => TreeDirection::Descendants,
+                    TreeDirection::Descendants => TreeDirection::Ancestors,
+                };
+                self.collapsed.clear();
+                self.update_rows(context);
+                self.row_list.select(self.root);
+            }
+            Some(Action::Confirm) => {
+                return LessonTreeAction::Terminate(self.row_list.currently_selected_id())
+            }
+            Some(Action::Cancel) => return LessonTreeAction::Terminate(None),
+            _ => self.row_list.handle_key(context.keymap, key),
+        }
+        LessonTreeAction::Noop
+    }
+}
+
+impl LessonTree {
+    pub fn render(&self, context: Context<'_>, area: Rect, frame: &mut Frame<'_>) {
+        let root_name = &context.lessons[&self.root].lesson.name;
+        let title = match self.direction {
+            TreeDirection::Ancestors => format!("Prerequisites of {root_name}"),
+            TreeDirection::Descendants => format!("Lessons depending on {root_name}"),
+        };
+        let block = Block::new()
+            .title(title)
+            .title_alignment(Alignment::Center)
+            .borders(Borders::ALL)
+            .border_style(Style::new().bold());
+
+        let inner = block.inner(area);
+        frame.render_widget(block, area);
+
+        let layout =
+            Layout::vertical([Constraint::Percentage(100), Constraint::Min(1)]).split(inner);
+
+        let items = self.rows.iter().map(|row| {
+            let id = *row.path.last().unwrap();
+            let node = &context.lessons[&id];
+
+            let marker = if !row.has_children || row.repeated {
+                "  "
+            } else if self.collapsed.contains(&row.path) {
+                "▸ "
+            } else {
+                "▾ "
+            };
+            let mut spans = vec![
+                Span::raw("  ".repeat(row.path.len() - 1)),
+                Span::raw(marker),
+            ];
+            spans.extend(
+                context
+                    .theme
+                    .lesson_name(&node.lesson.name, &node.status)
+                    .spans,
+            );
+            if row.repeated && row.has_children {
+                spans.push(Span::raw(" (see above)").italic().dim());
+            }
+            ListItem::new(Line::from(spans))
+        });
+
+        let list = List::new(items).highlight_style(Style::default().reversed());
+        frame.render_stateful_widget(
+            list,
+            layout[0],
+            &mut self.row_list.list_state_refcell().borrow_mut(),
+        );
+
+        let help = format!(
+            "Type {} to collapse, {} to show {}",
+            context
+                .keymap
+                .describe_keys(KeyContext::LessonTree, Action::ToggleCollapse),
+            context
+                .keymap
+                .describe_keys(KeyContext::LessonTree, Action::ToggleTreeDirection),
+            match self.direction {
+                TreeDirection::Ancestors => "dependents",
+                TreeDirection::Descendants => "prerequisites",
+            }
+        );
+        frame.render_widget(Text::from(help), layout[1]);
+    }
+}
@@ -1,5 +1,13 @@
+pub mod bulk_actions;
 pub mod fuzzyfinder;
+pub mod help;
+pub mod learning_path;
 pub mod lesson_edit_form;
+pub mod lesson_splitter;
+pub mod lesson_tree;
 pub mod node_list;
+pub mod review_prompt;
+pub mod sort_selector;
+pub mod statistics;
 pub mod study_editor;
 pub mod textinput;
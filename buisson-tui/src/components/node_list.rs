@@ -1,14 +1,29 @@
-use std::cell::RefCell;
+use std::{
+    cell::{Cell, RefCell},
+    collections::HashSet,
+};
 
-use crossterm::event::{KeyCode, KeyEvent};
-use ratatui::widgets::ListState;
+use crossterm::event::{KeyEvent, MouseButton, MouseEvent, MouseEventKind};
+use ratatui::{
+    layout::{Position, Rect},
+    widgets::ListState,
+};
 
 use buisson_common::Id;
 
+use crate::keymap::{Action, KeyContext, Keymap};
+
 #[derive(Debug)]
 pub struct NodeList {
     ids: Vec<Id>,
     list_state: RefCell<ListState>,
+    /// the lessons marked to apply an action to all of them at once
+    marked: HashSet<Id>,
+    /// the lesson a visual selection was started from, if one is in progress. Every lesson
+    /// between it and the selected one is marked along with the others.
+    visual_anchor: Option<Id>,
+    /// where the lessons were last drawn, to find the one under a mouse click
+    area: Cell<Rect>,
 }
 
 impl NodeList {
@@ -18,6 +33,9 @@ impl NodeList {
         Self {
             ids,
             list_state: RefCell::new(list_state),
+            marked: HashSet::new(),
+            visual_anchor: None,
+            area: Cell::default(),
         }
     }
 
@@ -25,7 +43,7 @@ impl NodeList {
         self.list_state
             .borrow()
             .selected()
-            .map(|list_index| self.ids[list_index])
+            .and_then(|list_index| self.ids.get(list_index).copied())
     }
 
     pub fn ids(&self) -> &[Id] {
@@ -36,6 +54,12 @@ impl NodeList {
         &self.list_state
     }
 
+    /// Remember that the lessons were drawn in `area`, one per row, so that mouse events can be
+    /// handled. This is the area inside the borders of the list, if it has some.
+    pub fn set_area(&self, area: Rect) {
+        self.area.set(area);
+    }
+
     pub fn select(&mut self, id: Id) {
         let index = self.ids.iter().position(|&m_id| id == m_id);
 
@@ -43,24 +67,139 @@ impl NodeList {
     }
 
     pub fn remove_node(&mut self, id: Id) {
-        self.ids.retain(|&x| x != id)
+        self.ids.retain(|&x| x != id);
+        self.marked.remove(&id);
+        if self.visual_anchor == Some(id) {
+            self.visual_anchor = None;
+        }
     }
 
     pub fn push(&mut self, id: Id) {
         self.ids.push(id);
     }
 
+    /// Replace the lessons of the list. Marked lessons that are still in it stay marked.
     pub fn change_values(&mut self, new_values: Vec<Id>) {
         self.ids = new_values;
+        self.marked.retain(|id| self.ids.contains(id));
+        if self
+            .visual_anchor
+            .is_some_and(|anchor| !self.ids.contains(&anchor))
+        {
+            self.visual_anchor = None;
+        }
     }
 }
 
+// marking lessons
 impl NodeList {
-    pub fn handle_key(&mut self, key: &KeyEvent) {
-        match key.code {
-            KeyCode::Char('j') => self.list_state.get_mut().select_next(),
-            KeyCode::Char('k') => self.list_state.get_mut().select_previous(),
+    /// The lessons between the start of the visual selection and the selected one, included.
+    fn visual_range(&self) -> &[Id] {
+        let anchor = self
+            .visual_anchor
+            .and_then(|anchor| self.ids.iter().position(|&id| id == anchor));
+        let selected = self.list_state.borrow().selected();
+        match (anchor, selected) {
+            (Some(anchor), Some(selected)) => {
+                let selected = std::cmp::min(selected, self.ids.len().saturating_sub(1));
+                let start = std::cmp::min(anchor, selected);
+                let end = std::cmp::max(anchor, selected);
+                &self.ids[start..=end]
+            }
+            _ => &[],
+        }
+    }
+
+    /// Mark the selected lesson, or unmark it if it already is.
+    pub fn toggle_mark(&mut self) {
+        if let Some(id) = self.currently_selected_id() {
+            if !self.marked.remove(&id) {
+                self.marked.insert(id);
+            }
+        }
+    }
+
+    /// Start a visual selection from the selected lesson, or end the current one by marking every
+    /// lesson it covers.
+    pub fn toggle_visual_selection(&mut self) {
+        if self.visual_anchor.is_some() {
+            self.end_visual_selection();
+        } else {
+            self.visual_anchor = self.currently_selected_id();
+        }
+    }
+
+    /// Mark every lesson the visual selection covers, if one is in progress, and end it.
+    pub fn end_visual_selection(&mut self) {
+        let range = self.visual_range().to_vec();
+        self.marked.extend(range);
+        self.visual_anchor = None;
+    }
+
+    /// Mark every lesson of `ids` that is in the list.
+    pub fn mark(&mut self, ids: impl IntoIterator<Item = Id>) {
+        let ids = ids
+            .into_iter()
+            .filter(|id| self.ids.contains(id))
+            .collect::<Vec<_>>();
+        self.marked.extend(ids);
+    }
+
+    /// Unmark every lesson, and stop the visual selection.
+    pub fn clear_marks(&mut self) {
+        self.marked.clear();
+        self.visual_anchor = None;
+    }
+
+    /// The marked lessons, including the ones covered by the visual selection.
+    pub fn marked_set(&self) -> HashSet<Id> {
+        self.marked
+            .iter()
+            .chain(self.visual_range())
+            .copied()
+            .collect()
+    }
+
+    /// The marked lessons, including the ones covered by the visual selection, in the order of
+    /// the list.
+    pub fn marked_ids(&self) -> Vec<Id> {
+        let marked = self.marked_set();
+        self.ids
+            .iter()
+            .copied()
+            .filter(|id| marked.contains(id))
+            .collect()
+    }
+}
+
+impl NodeList {
+    pub fn handle_key(&mut self, keymap: &Keymap, key: &KeyEvent) {
+        match keymap.action(KeyContext::List, key) {
+            Some(Action::SelectNext) => self.list_state.get_mut().select_next(),
+            Some(Action::SelectPrevious) => self.list_state.get_mut().select_previous(),
+            _ => (),
+        }
+    }
+
+    /// Handle a mouse event: a click selects the lesson under it, and the wheel moves the
+    /// selection. Return whether the event happened over the list.
+    pub fn handle_mouse(&mut self, mouse: &MouseEvent) -> bool {
+        let area = self.area.get();
+        if !area.contains(Position::new(mouse.column, mouse.row)) {
+            return false;
+        }
+        let list_state = self.list_state.get_mut();
+        match mouse.kind {
+            MouseEventKind::Down(MouseButton::Left) => {
+                let index = list_state.offset() + usize::from(mouse.row - area.y);
+                if index < self.ids.len() {
+                    list_state.select(Some(index));
+                }
+            }
+            MouseEventKind::ScrollDown => list_state.select_next(),
+            MouseEventKind::ScrollUp => list_state.select_previous(),
             _ => (),
         }
+        true
     }
 }
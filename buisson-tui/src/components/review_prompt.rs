@@ -0,0 +1,153 @@
+use std::collections::HashSet;
+
+use crossterm::event::KeyEvent;
+use ratatui::{
+    layout::{Alignment, Constraint, Layout, Rect},
+    style::{Style, Stylize},
+    text::{Line, Span, Text},
+    widgets::{Block, Borders, List, ListItem},
+    Frame,
+};
+
+use buisson_common::Id;
+
+use crate::{
+    app::Context,
+    keymap::{Action, KeyContext},
+};
+
+use super::node_list::NodeList;
+
+/// A popup shown after a lesson was failed, to choose which of its prerequisites should be
+/// reviewed early, as they may have been forgotten too.
+#[derive(Debug)]
+pub struct ReviewPrompt {
+    /// the lesson that was failed
+    lesson: Id,
+    prerequisites: NodeList,
+    /// the prerequisites to review early
+    chosen: HashSet<Id>,
+}
+
+pub enum ReviewPromptAction {
+    Noop,
+    /// The prompt should be closed, and the given lessons reviewed early. The list is empty if
+    /// the user skipped the prompt.
+    Terminate(Vec<Id>),
+}
+
+impl ReviewPrompt {
+    /// Create a prompt for the failed lesson `lesson`, offering to review `prerequisites` early.
+    /// They are all chosen at first.
+    pub fn new(lesson: Id, prerequisites: Vec<Id>) -> Self {
+        Self {
+            lesson,
+            chosen: prerequisites.iter().copied().collect(),
+            prerequisites: NodeList::new(prerequisites),
+        }
+    }
+
+    fn title(&self, context: &Context<'_>) -> String {
+        format!(
+            "{} was failed. Review these early?",
+            context.lessons[&self.lesson].lesson.name
+        )
+    }
+
+    /// The size the popup needs, as (width, height).
+    pub fn size(&self, context: Context<'_>) -> (u16, u16) {
+        let help_width = self.help(&context).chars().count();
+        let width = self
+            .prerequisites
+            .ids()
+            .iter()
+            .map(|id| context.lessons[id].lesson.name.chars().count() + 6)
+            .chain([self.title(&context).chars().count(), help_width])
+            .max()
+            .unwrap_or_default();
+        (width as u16 + 4, self.prerequisites.ids().len() as u16 + 3)
+    }
+
+    fn help(&self, context: &Context<'_>) -> String {
+        format!(
+            "Type {} to choose, {} to confirm, {} to skip",
+            context
+                .keymap
+                .describe_keys(KeyContext::ReviewPrompt, Action::ToggleSelection),
+            context
+                .keymap
+                .describe_keys(KeyContext::ReviewPrompt, Action::Confirm),
+            context
+                .keymap
+                .describe_keys(KeyContext::ReviewPrompt, Action::Cancel),
+        )
+    }
+}
+
+impl ReviewPrompt {
+    pub fn handle_key(&mut self, context: Context<'_>, key: &KeyEvent) -> ReviewPromptAction {
+        match context.keymap.action(KeyContext::ReviewPrompt, key) {
+            Some(Action::ToggleSelection) => {
+                if let Some(id) = self.prerequisites.currently_selected_id() {
+                    if !self.chosen.remove(&id) {
+                        self.chosen.insert(id);
+                    }
+                }
+            }
+            Some(Action::Confirm) => {
+                let chosen = self
+                    .prerequisites
+                    .ids()
+                    .iter()
+                    .copied()
+                    .filter(|id| self.chosen.contains(id))
+                    .collect();
+                return ReviewPromptAction::Terminate(chosen);
+            }
+            Some(Action::Cancel) => return ReviewPromptAction::Terminate(vec![]),
+            _ => self.prerequisites.handle_key(context.keymap, key),
+        }
+        ReviewPromptAction::Noop
+    }
+}
+
+impl ReviewPrompt {
+    pub fn render(&self, context: Context<'_>, area: Rect, frame: &mut Frame<'_>) {
+        let block = Block::new()
+            .title(self.title(&context))
+            .title_alignment(Alignment::Center)
+            .borders(Borders::ALL)
+            .border_style(Style::new().bold());
+
+        let inner = block.inner(area);
+        frame.render_widget(block, area);
+
+        let layout =
+            Layout::vertical([Constraint::Percentage(100), Constraint::Min(1)]).split(inner);
+
+        let items = self.prerequisites.ids().iter().map(|id| {
+            let node = &context.lessons[id];
+            let checkbox = if self.chosen.contains(id) {
+                "[x] "
+            } else {
+                "[ ] "
+            };
+            let mut spans = vec![Span::raw(checkbox)];
+            spans.extend(
+                context
+                    .theme
+                    .lesson_name(&node.lesson.name, &node.status)
+                    .spans,
+            );
+            ListItem::new(Line::from(spans))
+        });
+
+        let list = List::new(items).highlight_style(Style::default().reversed());
+        frame.render_stateful_widget(
+            list,
+            layout[0],
+            &mut self.prerequisites.list_state_refcell().borrow_mut(),
+        );
+        frame.render_widget(Text::from(self.help(&context)), layout[1]);
+    }
+}
@@ -0,0 +1,80 @@
+use std::cell::RefCell;
+
+use crossterm::event::KeyEvent;
+use ratatui::{
+    layout::{Alignment, Rect},
+    style::{Style, Stylize},
+    text::Line,
+    widgets::{Block, Borders, List, ListState},
+    Frame,
+};
+
+use buisson_common::sort::SortOrder;
+
+use crate::keymap::{Action, KeyContext, Keymap};
+
+/// A popup to choose the order of the lesson list.
+#[derive(Debug)]
+pub struct SortSelector {
+    list_state: RefCell<ListState>,
+}
+
+pub enum SortSelectorAction {
+    Noop,
+    /// The selector should be closed, and the user chose either nothing (`None`) or the given
+    /// order.
+    Terminate(Option<SortOrder>),
+}
+
+impl SortSelector {
+    pub fn new(current: SortOrder) -> Self {
+        let index = SortOrder::ALL.iter().position(|&order| order == current);
+        Self {
+            list_state: RefCell::new(ListState::default().with_selected(index)),
+        }
+    }
+
+    fn selected(&self) -> Option<SortOrder> {
+        self.list_state
+            .borrow()
+            .selected()
+            .and_then(|index| SortOrder::ALL.get(index).copied())
+    }
+
+    pub fn handle_key(&mut self, keymap: &Keymap, key: &KeyEvent) -> SortSelectorAction {
+        match keymap.action(KeyContext::SortSelector, key) {
+            Some(Action::Confirm) => return SortSelectorAction::Terminate(self.selected()),
+            Some(Action::Cancel) => return SortSelectorAction::Terminate(None),
+            _ => (),
+        }
+        match keymap.action(KeyContext::List, key) {
+            Some(Action::SelectNext) => self.list_state.get_mut().select_next(),
+            Some(Action::SelectPrevious) => self.list_state.get_mut().select_previous(),
+            _ => (),
+        }
+        SortSelectorAction::Noop
+    }
+
+    /// The size the popup needs, as (width, height).
+    pub fn size() -> (u16, u16) {
+        let width = SortOrder::ALL
+            .iter()
+            .map(|order| order.description().len())
+            .max()
+            .unwrap_or_default();
+        (width as u16 + 4, SortOrder::ALL.len() as u16 + 2)
+    }
+
+    pub fn render(&self, area: Rect, frame: &mut Frame<'_>) {
+        let block = Block::new()
+            .title(Line::from("Sort by").alignment(Alignment::Center))
+            .borders(Borders::ALL)
+            .border_style(Style::default().bold());
+
+        let list = List::new(SortOrder::ALL.iter().map(|order| order.description()))
+            .block(block)
+            .highlight_style(Style::default().reversed());
+
+        frame.render_stateful_widget(list, area, &mut self.list_state.borrow_mut());
+    }
+}
@@ -0,0 +1,281 @@
+use std::time::Duration;
+
+use crossterm::event::KeyEvent;
+use ratatui::{
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
+    style::{Style, Stylize},
+    text::{Line, Text},
+    widgets::{Bar, BarChart, BarGroup, Block, Borders},
+    Frame,
+};
+
+use buisson_common::{stats::Statistics, Id, NodeStatus};
+
+use crate::{
+    app::Context,
+    keymap::{Action, KeyContext, Keymap},
+};
+
+/// A screen showing statistics about the lessons and the review history.
+#[derive(Debug)]
+pub struct StatisticsScreen {
+    stats: Statistics,
+}
+
+pub enum StatisticsScreenAction {
+    Noop,
+    Terminate,
+}
+
+/// Format `duration` like a clock: `m:ss`, or `h:mm:ss` past an hour.
+pub fn format_duration(duration: Duration) -> String {
+    let seconds = duration.as_secs();
+    let (hours, minutes, seconds) = (seconds / 3600, seconds / 60 % 60, seconds % 60);
+    if hours > 0 {
+        format!("{}:{:02}:{:02}", hours, minutes, seconds)
+    } else {
+        format!("{}:{:02}", minutes, seconds)
+    }
+}
+
+fn chart_block(title: String) -> Block<'static> {
+    Block::new()
+        .title(title)
+        .title_alignment(Alignment::Center)
+        .borders(Borders::ALL)
+}
+
+impl StatisticsScreen {
+    pub fn new(stats: Statistics) -> Self {
+        Self { stats }
+    }
+
+    pub fn handle_key(&mut self, keymap: &Keymap, key: &KeyEvent) -> StatisticsScreenAction {
+        match keymap.action(KeyContext::Statistics, key) {
+            Some(Action::Cancel) => StatisticsScreenAction::Terminate,
+            _ => StatisticsScreenAction::Noop,
+        }
+    }
+}
+
+impl StatisticsScreen {
+    pub fn render(&self, context: Context<'_>, area: Rect, frame: &mut Frame<'_>) {
+        let block = Block::new()
+            .title("Statistics")
+            .title_alignment(Alignment::Center)
+            .borders(Borders::ALL)
+            .border_style(Style::new().bold());
+        let inner = block.inner(area);
+        frame.render_widget(block, area);
+
+        let layout = Layout::vertical([
+            Constraint::Percentage(30),
+            Constraint::Percentage(25),
+            Constraint::Percentage(25),
+            Constraint::Percentage(20),
+            Constraint::Min(1),
+            Constraint::Min(1),
+        ])
+        .split(inner);
+        let top = Layout::horizontal(Constraint::from_percentages([50, 50])).split(layout[0]);
+        let rankings = Layout::horizontal(Constraint::from_percentages([50, 50])).split(layout[2]);
+        let times = Layout::horizontal(Constraint::from_percentages([50, 50])).split(layout[3]);
+
+        self.render_levels(top[0], frame);
+        self.render_statuses(context.clone(), top[1], frame);
+        self.render_reviews(layout[1], frame);
+        self.render_ranking(
+            context.clone(),
+            "Most overdue lessons (days late)",
+            &self.stats.most_overdue,
+            rankings[0],
+            frame,
+        );
+        self.render_ranking(
+            context.clone(),
+            "Lessons blocking the most dependents",
+            &self.stats.most_blocking,
+            rankings[1],
+            frame,
+        );
+        self.render_time_per_lesson(context.clone(), times[0], frame);
+        self.render_time_per_tag(times[1], frame);
+        frame.render_widget(Text::from(self.time_summary()), layout[4]);
+
+        frame.render_widget(
+            Text::from(format!(
+                "Type {} to close",
+                context
+                    .keymap
+                    .describe_keys(KeyContext::Statistics, Action::Cancel)
+            )),
+            layout[5],
+        );
+    }
+
+    fn time_summary(&self) -> String {
+        match (self.stats.average_review_time, self.stats.todays_queue_time) {
+            (Some(average), Some(queue)) => format!(
+                "Average review: {}    Today's queue: about {}",
+                format_duration(average),
+                format_duration(queue)
+            ),
+            _ => String::from("No study session was timed yet"),
+        }
+    }
+
+    fn render_levels(&self, area: Rect, frame: &mut Frame<'_>) {
+        let bars = self
+            .stats
+            .levels
+            .iter()
+            .map(|(level, count)| {
+                Bar::default()
+                    .value(*count as u64)
+                    .label(Line::from(level.to_string()))
+            })
+            .collect::<Vec<_>>();
+
+        let chart = BarChart::default()
+            .block(chart_block(String::from("Practiced lessons per step")))
+            .data(BarGroup::default().bars(&bars))
+            .bar_width(3)
+            .bar_gap(1);
+        frame.render_widget(chart, area);
+    }
+
+    fn render_statuses(&self, context: Context<'_>, area: Rect, frame: &mut Frame<'_>) {
+        let stats = &self.stats;
+        let lesson_bars = [
+            ("New", stats.not_practiced),
+            ("Known", stats.good_enough),
+            ("Studied", stats.practiced),
+        ]
+        .map(|(label, count)| Bar::default().value(count as u64).label(Line::from(label)));
+        let node_bars = [
+            ("Ok", stats.ok, NodeStatus::Ok),
+            ("Pending", stats.pending, NodeStatus::Pending),
+            (
+                "Missing",
+                stats.missing_prereq,
+                NodeStatus::MissingPrereq(vec![]),
+            ),
+        ]
+        .map(|(label, count, status)| {
+            Bar::default()
+                .value(count as u64)
+                .label(Line::from(label))
+                .style(context.theme.style_from_status(&status))
+        });
+
+        let chart = BarChart::default()
+            .block(chart_block(String::from("Lessons per status")))
+            .data(BarGroup::default().bars(&lesson_bars))
+            .data(BarGroup::default().bars(&node_bars))
+            .bar_width(7)
+            .bar_gap(1)
+            .group_gap(3);
+        frame.render_widget(chart, area);
+    }
+
+    fn render_reviews(&self, area: Rect, frame: &mut Frame<'_>) {
+        let days = self.stats.reviews_per_day.len().max(1) as u16;
+        // leave room for the borders and a gap between bars
+        let bar_width = (area.width.saturating_sub(2) / days)
+            .saturating_sub(1)
+            .max(1);
+
+        let bars = self
+            .stats
+            .reviews_per_day
+            .iter()
+            .map(|(date, count)| {
+                let bar = Bar::default().value(*count as u64);
+                if bar_width >= 2 {
+                    bar.label(Line::from(date.format("%d").to_string()))
+                } else {
+                    bar
+                }
+            })
+            .collect::<Vec<_>>();
+
+        let title = format!(
+            "Reviews per day (streak: {} days, longest: {} days)",
+            self.stats.current_streak, self.stats.longest_streak
+        );
+        let chart = BarChart::default()
+            .block(chart_block(title))
+            .data(BarGroup::default().bars(&bars))
+            .bar_width(bar_width)
+            .bar_gap(1);
+        frame.render_widget(chart, area);
+    }
+
+    fn render_ranking<V: Copy + TryInto<u64>>(
+        &self,
+        context: Context<'_>,
+        title: &str,
+        ranking: &[(Id, V)],
+        area: Rect,
+        frame: &mut Frame<'_>,
+    ) {
+        let bars = ranking
+            .iter()
+            .filter_map(|(id, value)| {
+                let node = context.lessons.get(id)?;
+                Some(
+                    Bar::default()
+                        .value((*value).try_into().unwrap_or_default())
+                        .label(Line::from(node.lesson.name.as_str()))
+                        .style(context.theme.style_from_status(&node.status)),
+                )
+            })
+            .collect::<Vec<_>>();
+        render_horizontal_chart(title, &bars, area, frame);
+    }
+
+    fn render_time_per_lesson(&self, context: Context<'_>, area: Rect, frame: &mut Frame<'_>) {
+        let bars = self
+            .stats
+            .time_per_lesson
+            .iter()
+            .filter_map(|(id, time)| {
+                let node = context.lessons.get(id)?;
+                Some(
+                    time_bar(*time)
+                        .label(Line::from(node.lesson.name.as_str()))
+                        .style(context.theme.style_from_status(&node.status)),
+                )
+            })
+            .collect::<Vec<_>>();
+        render_horizontal_chart("Time spent per lesson", &bars, area, frame);
+    }
+
+    fn render_time_per_tag(&self, area: Rect, frame: &mut Frame<'_>) {
+        let bars = self
+            .stats
+            .time_per_tag
+            .iter()
+            .map(|(tag, time)| time_bar(*time).label(Line::from(tag.as_str())))
+            .collect::<Vec<_>>();
+        render_horizontal_chart("Time spent per tag", &bars, area, frame);
+    }
+}
+
+/// A bar showing `time`, in seconds.
+fn time_bar(time: Duration) -> Bar<'static> {
+    Bar::default()
+        .value(time.as_secs())
+        .text_value(format_duration(time))
+}
+
+/// Render `bars` as a chart with one bar per line.
+fn render_horizontal_chart(title: &str, bars: &[Bar<'_>], area: Rect, frame: &mut Frame<'_>) {
+    let chart = BarChart::default()
+        .block(chart_block(String::from(title)))
+        .data(BarGroup::default().bars(bars))
+        .direction(Direction::Horizontal)
+        .bar_width(1)
+        .bar_gap(0);
+    frame.render_widget(chart, area);
+}
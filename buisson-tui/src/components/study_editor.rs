@@ -1,13 +1,24 @@
-use crossterm::event::{KeyCode, KeyEvent};
+use std::cell::RefCell;
+
+use crossterm::event::{KeyEvent, MouseButton, MouseEvent, MouseEventKind};
 use rand::thread_rng;
 use ratatui::{
-    layout::{Constraint, Layout, Rect},
+    layout::{Constraint, Layout, Position, Rect},
     style::{Style, Stylize},
     text::{Line, Span, Text},
     Frame,
 };
 
-use buisson_common::LessonStatus;
+use buisson_common::{grade::Grade, LessonStatus};
+
+use crate::keymap::{Action, KeyContext, Keymap};
+
+/// Whether the outcome of the session is given as a grade, from which the new status is computed,
+/// or the new status is chosen by hand.
+enum StudyEditorMode {
+    Grading,
+    ManualOverride,
+}
 
 enum StudyEditorState {
     GoodEnough,
@@ -15,26 +26,52 @@ enum StudyEditorState {
     Practiced,
 }
 
+/// An option of the editor, that can be clicked.
+#[derive(Clone, Copy)]
+enum StudyEditorOption {
+    Grade(Grade),
+    NotPracticed,
+    Practiced(u32),
+    GoodEnough,
+}
+
 impl StudyEditor {
-    fn to_lesson_status(&self) -> LessonStatus {
-        match self.state {
+    /// The new status of the lesson, along with the grade it was computed from, if any.
+    fn to_lesson_status(&self) -> (LessonStatus, Option<Grade>) {
+        if let StudyEditorMode::Grading = self.mode {
+            let status = self
+                .status
+                .new_status_if_graded(self.grade, &mut thread_rng());
+            return (status, Some(self.grade));
+        }
+        let status = match self.state {
             StudyEditorState::GoodEnough => LessonStatus::GoodEnough,
             StudyEditorState::NotPracticed => LessonStatus::NotPracticed,
             StudyEditorState::Practiced => {
                 LessonStatus::new_status_if_studied(self.step, &mut thread_rng())
             }
-        }
+        };
+        (status, None)
     }
 }
 
 pub struct StudyEditor {
+    /// the status of the lesson before this session.
+    status: LessonStatus,
+    mode: StudyEditorMode,
+    grade: Grade,
+    /// the status chosen by hand, when overriding the grade.
     state: StudyEditorState,
     step: u32,
+    /// where the options were last drawn, to find the one under a mouse click
+    options: RefCell<Vec<(Rect, StudyEditorOption)>>,
 }
 
 pub enum StudyEditorAction {
     Noop,
-    Terminate(Option<LessonStatus>),
+    /// The session is over, and the lesson either keeps its status (`None`) or gets the given
+    /// one, computed from the given grade if it was graded.
+    Terminate(Option<(LessonStatus, Option<Grade>)>),
 }
 
 impl StudyEditor {
@@ -49,9 +86,66 @@ impl StudyEditor {
             LessonStatus::Practiced { level, .. } => level + 1,
             LessonStatus::NotPracticed | LessonStatus::GoodEnough => 0,
         };
-        Self { state, step }
+        Self {
+            status,
+            mode: StudyEditorMode::Grading,
+            grade: Grade::Good,
+            state,
+            step,
+            options: RefCell::default(),
+        }
+    }
+
+    pub fn render(&self, keymap: &Keymap, area: Rect, frame: &mut Frame<'_>) {
+        self.options.borrow_mut().clear();
+        match self.mode {
+            StudyEditorMode::Grading => self.render_grading(keymap, area, frame),
+            StudyEditorMode::ManualOverride => self.render_manual_override(area, frame),
+        }
     }
-    pub fn render(&self, area: Rect, frame: &mut Frame<'_>) {
+
+    fn render_grading(&self, keymap: &Keymap, area: Rect, frame: &mut Frame<'_>) {
+        let layout = Layout::vertical(Constraint::from_mins([1, 1, 1])).split(area);
+
+        let grades_layout =
+            Layout::horizontal(Constraint::from_ratios([(1, 4); 4])).split(layout[0]);
+        for (grade, grade_area) in Grade::ALL.iter().zip(grades_layout.iter()) {
+            let style = if *grade == self.grade {
+                Style::default().reversed()
+            } else {
+                Style::default()
+            };
+            frame.render_widget(
+                Line::from(grade.description()).style(style).centered(),
+                *grade_area,
+            );
+            self.options
+                .borrow_mut()
+                .push((*grade_area, StudyEditorOption::Grade(*grade)));
+        }
+
+        let preview = match (
+            self.status.level_after(self.grade),
+            self.status.interval_after(self.grade),
+        ) {
+            (Some(level), Some(interval)) => format!(
+                "Step {}, good for about {} day{}",
+                level,
+                interval,
+                if interval == 1 { "" } else { "s" }
+            ),
+            _ => String::from("Stays known"),
+        };
+        frame.render_widget(Line::from(preview).centered(), layout[1]);
+
+        let help = format!(
+            "Type {} to choose the outcome by hand",
+            keymap.describe_keys(KeyContext::StudyEditor, Action::ManualOverride)
+        );
+        frame.render_widget(Line::from(help).centered().dim(), layout[2]);
+    }
+
+    fn render_manual_override(&self, area: Rect, frame: &mut Frame<'_>) {
         let not_practiced_text = if let StudyEditorState::NotPracticed = self.state {
             Text::from("Not Practiced").style(Style::default().reversed())
         } else {
@@ -111,40 +205,110 @@ impl StudyEditor {
         frame.render_widget(not_practiced_text, area_left);
         frame.render_widget(practiced_text, area_middle);
         frame.render_widget(good_enough_text, area_right);
+
+        let mut options = self.options.borrow_mut();
+        options.push((area_left, StudyEditorOption::NotPracticed));
+        let steps = self.step.saturating_sub(1)..=self.step + 1;
+        for (row, step) in area_middle.rows().zip(steps) {
+            options.push((row, StudyEditorOption::Practiced(step)));
+        }
+        options.push((area_right, StudyEditorOption::GoodEnough));
     }
 
-    pub fn handle_key(&mut self, key: &KeyEvent) -> StudyEditorAction {
-        match key.code {
-            KeyCode::Char('l') | KeyCode::Tab => match self.state {
+    pub fn handle_key(&mut self, keymap: &Keymap, key: &KeyEvent) -> StudyEditorAction {
+        let action = keymap.action(KeyContext::StudyEditor, key);
+        if let StudyEditorMode::Grading = self.mode {
+            let index = Grade::ALL.iter().position(|&grade| grade == self.grade);
+            let index = index.unwrap_or_default();
+            match action {
+                Some(Action::MoveRight) => {
+                    self.grade = Grade::ALL[std::cmp::min(index + 1, Grade::ALL.len() - 1)];
+                }
+                Some(Action::MoveLeft) => self.grade = Grade::ALL[index.saturating_sub(1)],
+                Some(Action::ManualOverride) => self.mode = StudyEditorMode::ManualOverride,
+                Some(Action::Confirm) => {
+                    return StudyEditorAction::Terminate(Some(self.to_lesson_status()));
+                }
+                Some(Action::Cancel) => return StudyEditorAction::Terminate(None),
+                _ => (),
+            }
+            return StudyEditorAction::Noop;
+        }
+
+        match action {
+            Some(Action::ManualOverride) => self.mode = StudyEditorMode::Grading,
+            Some(Action::MoveRight) => match self.state {
                 StudyEditorState::GoodEnough => (),
                 StudyEditorState::NotPracticed => self.state = StudyEditorState::Practiced,
                 StudyEditorState::Practiced => self.state = StudyEditorState::GoodEnough,
             },
-            KeyCode::Char('h') | KeyCode::BackTab => match self.state {
+            Some(Action::MoveLeft) => match self.state {
                 StudyEditorState::GoodEnough => self.state = StudyEditorState::Practiced,
                 StudyEditorState::NotPracticed => (),
                 StudyEditorState::Practiced => self.state = StudyEditorState::NotPracticed,
             },
-            KeyCode::Char('j') => {
+            Some(Action::IncreaseStep) => {
                 if let StudyEditorState::Practiced = self.state {
                     self.step += 1;
                 }
             }
-            KeyCode::Char('k') => {
+            Some(Action::DecreaseStep) => {
                 if let StudyEditorState::Practiced = self.state {
                     if self.step > 0 {
                         self.step -= 1;
                     }
                 }
             }
-            KeyCode::Enter => {
+            Some(Action::Confirm) => {
                 return StudyEditorAction::Terminate(Some(self.to_lesson_status()));
             }
-            KeyCode::Esc => {
+            Some(Action::Cancel) => {
                 return StudyEditorAction::Terminate(None);
             }
             _ => (),
         }
         StudyEditorAction::Noop
     }
+
+    /// Handle a mouse event: a click on an option chooses it, and a second click on it ends the
+    /// session like confirming does.
+    pub fn handle_mouse(&mut self, mouse: &MouseEvent) -> StudyEditorAction {
+        if mouse.kind != MouseEventKind::Down(MouseButton::Left) {
+            return StudyEditorAction::Noop;
+        }
+        let position = Position::new(mouse.column, mouse.row);
+        let option = self
+            .options
+            .borrow()
+            .iter()
+            .find(|(area, _)| area.contains(position))
+            .map(|&(_, option)| option);
+        let Some(option) = option else {
+            return StudyEditorAction::Noop;
+        };
+
+        let chosen = match option {
+            StudyEditorOption::Grade(grade) => grade == self.grade,
+            StudyEditorOption::NotPracticed => {
+                matches!(self.state, StudyEditorState::NotPracticed)
+            }
+            StudyEditorOption::Practiced(step) => {
+                matches!(self.state, StudyEditorState::Practiced) && step == self.step
+            }
+            StudyEditorOption::GoodEnough => matches!(self.state, StudyEditorState::GoodEnough),
+        };
+        if chosen {
+            return StudyEditorAction::Terminate(Some(self.to_lesson_status()));
+        }
+        match option {
+            StudyEditorOption::Grade(grade) => self.grade = grade,
+            StudyEditorOption::NotPracticed => self.state = StudyEditorState::NotPracticed,
+            StudyEditorOption::Practiced(step) => {
+                self.state = StudyEditorState::Practiced;
+                self.step = step;
+            }
+            StudyEditorOption::GoodEnough => self.state = StudyEditorState::GoodEnough,
+        }
+        StudyEditorAction::Noop
+    }
 }
@@ -1,9 +1,14 @@
-use crossterm::event::{KeyCode, KeyEvent, KeyEventKind};
+use std::cell::Cell;
+
+use crossterm::event::{KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
 use ratatui::{
     layout::{Position, Rect},
     widgets::{Block, Paragraph},
     Frame,
 };
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+use crate::keymap::{Action, KeyContext, Keymap};
 
 #[derive(Default)]
 pub struct TextInputStyle<'a> {
@@ -29,26 +34,178 @@ impl<'a> TextInputStyle<'a> {
     }
 }
 
+/// A single line text field, edited like the prompt of a shell. When the text is too long for the
+/// field, it scrolls horizontally to keep the cursor in view.
 #[derive(Default, Debug)]
 pub struct TextInput {
     text: String,
+    /// the position of the cursor in `text`, in bytes. It is always on a character boundary.
+    cursor: usize,
+    /// the display column of the text shown at the left edge of the field, updated when rendering
+    scroll: Cell<usize>,
 }
 
 impl TextInput {
+    pub fn new(text: impl Into<String>) -> Self {
+        let text = text.into();
+        Self {
+            cursor: text.len(),
+            text,
+            scroll: Cell::default(),
+        }
+    }
+
     pub fn text(&self) -> &str {
         &self.text
     }
 
+    /// The display width of the text before the cursor, which is where the cursor is drawn when
+    /// the text is not scrolled.
+    pub fn cursor_column(&self) -> u16 {
+        self.text[..self.cursor].width() as u16
+    }
+
+    /// Insert `text` at the cursor, and move the cursor after it.
+    fn insert(&mut self, text: &str) {
+        self.text.insert_str(self.cursor, text);
+        self.cursor += text.len();
+    }
+
+    /// Delete the text between the cursor and `position`.
+    fn delete_to(&mut self, position: usize) {
+        let start = std::cmp::min(self.cursor, position);
+        let end = std::cmp::max(self.cursor, position);
+        self.text.replace_range(start..end, "");
+        self.cursor = start;
+    }
+
+    /// The position of the character before the cursor.
+    fn previous_char(&self) -> usize {
+        self.text[..self.cursor]
+            .char_indices()
+            .next_back()
+            .map_or(0, |(index, _)| index)
+    }
+
+    /// The position of the character after the cursor.
+    fn next_char(&self) -> usize {
+        self.text[self.cursor..]
+            .chars()
+            .next()
+            .map_or(self.cursor, |c| self.cursor + c.len_utf8())
+    }
+
+    /// The start of the word before the cursor, skipping the spaces right before it.
+    fn previous_word(&self) -> usize {
+        let before = self.text[..self.cursor].trim_end();
+        before
+            .char_indices()
+            .rev()
+            .find(|(_, c)| c.is_whitespace())
+            .map_or(0, |(index, c)| index + c.len_utf8())
+    }
+
+    /// The end of the word after the cursor, skipping the spaces right after it.
+    fn next_word(&self) -> usize {
+        let after = &self.text[self.cursor..];
+        let word = after.trim_start();
+        let spaces = after.len() - word.len();
+        let word_len = word.find(char::is_whitespace).unwrap_or(word.len());
+        self.cursor + spaces + word_len
+    }
+
+    pub fn handle_key(&mut self, keymap: &Keymap, key: &KeyEvent) {
+        if key.kind != KeyEventKind::Press {
+            return;
+        }
+
+        match keymap.action(KeyContext::TextInput, key) {
+            Some(Action::CursorLeft) => self.cursor = self.previous_char(),
+            Some(Action::CursorRight) => self.cursor = self.next_char(),
+            Some(Action::PreviousWord) => self.cursor = self.previous_word(),
+            Some(Action::NextWord) => self.cursor = self.next_word(),
+            Some(Action::LineStart) => self.cursor = 0,
+            Some(Action::LineEnd) => self.cursor = self.text.len(),
+            Some(Action::DeleteBackward) => self.delete_to(self.previous_char()),
+            Some(Action::DeleteForward) => self.delete_to(self.next_char()),
+            Some(Action::DeleteWord) => self.delete_to(self.previous_word()),
+            Some(Action::DeleteToStart) => self.delete_to(0),
+            Some(Action::DeleteToEnd) => self.delete_to(self.text.len()),
+            _ => {
+                if let KeyCode::Char(c) = key.code {
+                    if key.modifiers.difference(KeyModifiers::SHIFT).is_empty() {
+                        self.insert(c.encode_utf8(&mut [0; 4]));
+                    }
+                }
+            }
+        }
+    }
+
+    /// Insert pasted text at the cursor. The field only holds one line, so line breaks are
+    /// replaced with spaces.
+    pub fn handle_paste(&mut self, text: &str) {
+        let text = text
+            .lines()
+            .collect::<Vec<_>>()
+            .join(" ")
+            .chars()
+            .filter(|c| !c.is_control())
+            .collect::<String>();
+        self.insert(&text);
+    }
+}
+
+impl TextInput {
+    /// Scroll the text so that the cursor is visible in a field `width` columns wide, and return
+    /// the display column shown at its left edge.
+    fn scroll_to_cursor(&self, width: usize) -> usize {
+        let cursor = self.text[..self.cursor].width();
+        let mut scroll = self.scroll.get();
+        if cursor < scroll {
+            scroll = cursor;
+        } else if cursor >= scroll + width {
+            // the cursor takes a column of its own after the last character
+            scroll = cursor + 1 - width;
+        }
+        // don't leave the end of the field empty when the text could fill it
+        scroll = std::cmp::min(scroll, (self.text.width() + 1).saturating_sub(width));
+        self.scroll.set(scroll);
+        scroll
+    }
+
+    /// The part of the text shown between display columns `scroll` and `scroll + width`. A wide
+    /// character cut by the left edge is replaced with spaces, and one cut by the right edge is
+    /// left out.
+    fn visible_text(&self, scroll: usize, width: usize) -> String {
+        let mut visible = String::new();
+        let mut column = 0;
+        for c in self.text.chars() {
+            let end = column + c.width().unwrap_or(0);
+            if column >= scroll && end <= scroll + width {
+                visible.push(c);
+            } else if column < scroll && end > scroll {
+                visible.push_str(&" ".repeat(end - scroll));
+            }
+            column = end;
+        }
+        visible
+    }
+
     pub fn render(&self, area: Rect, frame: &mut Frame<'_>) {
         self.render_with_style(area, frame, TextInputStyle::default());
     }
 
     pub fn render_textinput(&self, area: Rect, frame: &mut Frame<'_>, display_cursor: bool) {
-        let text_widget = Paragraph::new(self.text.as_str());
+        if area.is_empty() {
+            return;
+        }
+        let width = usize::from(area.width);
+        let scroll = self.scroll_to_cursor(width);
+        let text_widget = Paragraph::new(self.visible_text(scroll, width));
 
         if display_cursor {
             frame.set_cursor_position(Position {
-                x: area.x + self.text_len(),
+                x: area.x + (usize::from(self.cursor_column()) - scroll) as u16,
                 y: area.y,
             });
         }
@@ -67,27 +224,84 @@ impl TextInput {
             None => self.render_textinput(area, frame, style.display_cursor),
         }
     }
+}
 
-    pub fn new(text: impl Into<String>) -> Self {
-        Self { text: text.into() }
-    }
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    pub fn handle_key(&mut self, key: &KeyEvent) {
-        if key.kind != KeyEventKind::Press {
-            return;
+    fn type_keys(input: &mut TextInput, keys: &[KeyEvent]) {
+        let keymap = Keymap::default();
+        for key in keys {
+            input.handle_key(&keymap, key);
         }
+    }
 
-        match key.code {
-            KeyCode::Char(c) => self.text.push(c),
-            KeyCode::Backspace => {
-                self.text.pop();
-            }
-            _ => (),
-        }
+    fn key(code: KeyCode) -> KeyEvent {
+        KeyEvent::new(code, KeyModifiers::NONE)
+    }
+
+    fn ctrl(c: char) -> KeyEvent {
+        KeyEvent::new(KeyCode::Char(c), KeyModifiers::CONTROL)
+    }
+
+    #[test]
+    fn test_editing() {
+        let mut input = TextInput::new("linear algebra");
+        type_keys(&mut input, &[ctrl('w'), key(KeyCode::Char('Z'))]);
+        assert_eq!(input.text(), "linear Z");
+
+        type_keys(
+            &mut input,
+            &[
+                key(KeyCode::Home),
+                key(KeyCode::Right),
+                key(KeyCode::Delete),
+                key(KeyCode::Char('é')),
+            ],
+        );
+        assert_eq!(input.text(), "lénear Z");
+        assert_eq!(input.cursor_column(), 2);
+
+        type_keys(&mut input, &[ctrl('k'), key(KeyCode::End), ctrl('u')]);
+        assert_eq!(input.text(), "");
+
+        let mut input = TextInput::new("a  b c");
+        type_keys(
+            &mut input,
+            &[
+                ctrl('a'),
+                KeyEvent::new(KeyCode::Right, KeyModifiers::CONTROL),
+            ],
+        );
+        type_keys(
+            &mut input,
+            &[KeyEvent::new(KeyCode::Right, KeyModifiers::CONTROL)],
+        );
+        type_keys(&mut input, &[key(KeyCode::Backspace)]);
+        assert_eq!(input.text(), "a   c");
     }
 
-    /// the length of the displayed text, in actual characters instead of bytes
-    pub fn text_len(&self) -> u16 {
-        self.text.chars().count() as u16
+    #[test]
+    fn test_paste() {
+        let mut input = TextInput::new("ab");
+        type_keys(&mut input, &[key(KeyCode::Left)]);
+        input.handle_paste("one\r\ntwo\n");
+        assert_eq!(input.text(), "aone twob");
+        assert_eq!(input.cursor_column(), 8);
+    }
+
+    #[test]
+    fn test_scrolling() {
+        let input = TextInput::new("日本語 text");
+        // the cursor is after the 11 columns of text, in a field 6 columns wide
+        assert_eq!(input.scroll_to_cursor(6), 6);
+        assert_eq!(input.visible_text(6, 6), " text");
+
+        let mut input = input;
+        type_keys(&mut input, &[key(KeyCode::Home), key(KeyCode::Right)]);
+        assert_eq!(input.scroll_to_cursor(6), 2);
+        assert_eq!(input.visible_text(2, 6), "本語 t");
+        assert_eq!(input.visible_text(1, 6), " 本語 ");
     }
 }
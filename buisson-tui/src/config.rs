@@ -0,0 +1,79 @@
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+use crate::{
+    keymap::{Action, KeyBinding, KeyContext, KeymapOverrides},
+    theme::ThemeName,
+};
+
+/// The user configuration, read from `config.ron` in the XDG config directory. Every field is
+/// optional, and missing ones take their default value. For instance:
+///
+/// ```ron
+/// (
+///     keybindings: {
+///         Browsing: { StudyLesson: ["enter"], Quit: ["q", "ctrl-c"] },
+///     },
+///     theme: ColorblindSafe,
+///     status_glyphs: true,
+///     leech_threshold: 6,
+///     review_prerequisites_of_failed: false,
+///     lessons_per_day: 5,
+///     lessons_directory: Some("/home/me/notes/lessons"),
+/// )
+/// ```
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// Keys to use instead of the default ones, per context and per action.
+    pub keybindings: KeymapOverrides,
+    /// The palette used to display the status of lessons.
+    pub theme: ThemeName,
+    /// Whether to display a symbol next to lesson names, so that their status can be read
+    /// without colors.
+    pub status_glyphs: bool,
+    /// How many lapses it takes for a lesson to be considered a leech, a lesson that keeps being
+    /// forgotten.
+    pub leech_threshold: u32,
+    /// Whether to offer to review the prerequisites of a lesson early when it is failed.
+    pub review_prerequisites_of_failed: bool,
+    /// How many lessons a day to assume when estimating how long a learning path takes.
+    pub lessons_per_day: u32,
+    /// A directory to store the lessons in, one file per lesson, instead of the database.
+    pub lessons_directory: Option<PathBuf>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            keybindings: KeymapOverrides::default(),
+            theme: ThemeName::default(),
+            status_glyphs: false,
+            leech_threshold: 4,
+            review_prerequisites_of_failed: true,
+            lessons_per_day: 3,
+            lessons_directory: None,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+    IOError(std::io::Error),
+    ParseError(ron::error::SpannedError),
+    /// the keybindings bind a key to two different actions of the same context: the key was
+    /// given to the first action, and is already bound to the second one.
+    KeyConflict(KeyContext, KeyBinding, Action, Action),
+}
+
+impl Config {
+    /// Read the configuration at `path`, or return the default one if there is no such file.
+    pub fn load(path: &Path) -> Result<Self, ConfigError> {
+        match std::fs::read_to_string(path) {
+            Ok(text) => ron::from_str(&text).map_err(ConfigError::ParseError),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(ConfigError::IOError(e)),
+        }
+    }
+}
@@ -0,0 +1,753 @@
+use std::collections::HashMap;
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use serde::Deserialize;
+
+use crate::config::ConfigError;
+
+/// The part of the interface a key press is handled by. Every context has its own set of
+/// bindings, so the same key can mean different things in different places.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+pub enum KeyContext {
+    /// The main lesson list, when nothing else is open.
+    Browsing,
+    /// Any list of lessons that can be navigated.
+    List,
+    /// The popups asking for confirmation before deleting lessons, or merging them.
+    DeletionConfirmation,
+    /// The popup used to record a study session.
+    StudyEditor,
+    /// The name field of the lesson editor.
+    LessonName,
+    /// The tags field of the lesson editor.
+    LessonTags,
+    /// The deadline field of the lesson editor.
+    LessonDeadline,
+    /// The prerequisite list of the lesson editor.
+    LessonPrerequisites,
+    /// The "OK" button of the lesson editor.
+    LessonValidation,
+    /// The search bar of a fuzzy finder.
+    SearchInput,
+    /// The result list of a fuzzy finder.
+    SearchResults,
+    /// The dependency tree of a lesson.
+    LessonTree,
+    /// The popup to choose the order of the lesson list.
+    SortSelector,
+    /// The statistics screen.
+    Statistics,
+    /// The prompt to review the prerequisites of a failed lesson early.
+    ReviewPrompt,
+    /// The checklist of the lessons to study to reach a lesson.
+    LearningPath,
+    /// The popup to choose what to do with the marked lessons.
+    BulkActions,
+    /// The prompt asking for a tag to add to or remove from the marked lessons.
+    TagPrompt,
+    /// The form to split a lesson into several parts.
+    LessonSplitter,
+    /// The name of a part of a lesson being split, while it is edited.
+    SplitPartName,
+    /// Any text field, while it is edited.
+    TextInput,
+    /// The help overlay, and the keys opening it from anywhere. Character keys don't open it
+    /// from text fields, where they are typed.
+    Help,
+}
+
+impl KeyContext {
+    /// A short name for the part of the interface, used to title help sections.
+    pub fn description(&self) -> &'static str {
+        match self {
+            KeyContext::Browsing => "Lesson list",
+            KeyContext::List => "Lists",
+            KeyContext::DeletionConfirmation => "Confirmation",
+            KeyContext::StudyEditor => "Study session",
+            KeyContext::LessonName => "Lesson name",
+            KeyContext::LessonTags => "Lesson tags",
+            KeyContext::LessonDeadline => "Lesson deadline",
+            KeyContext::LessonPrerequisites => "Lesson prerequisites",
+            KeyContext::LessonValidation => "Lesson editor button",
+            KeyContext::SearchInput => "Search bar",
+            KeyContext::SearchResults => "Search results",
+            KeyContext::LessonTree => "Dependency tree",
+            KeyContext::SortSelector => "Sort order",
+            KeyContext::Statistics => "Statistics",
+            KeyContext::ReviewPrompt => "Early review",
+            KeyContext::LearningPath => "Learning path",
+            KeyContext::BulkActions => "Marked lessons",
+            KeyContext::TagPrompt => "Tag",
+            KeyContext::LessonSplitter => "Lesson split",
+            KeyContext::SplitPartName => "Part name",
+            KeyContext::TextInput => "Text fields",
+            KeyContext::Help => "Help",
+        }
+    }
+}
+
+/// Everything a key can be bound to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+pub enum Action {
+    Quit,
+    AddLesson,
+    EditLesson,
+    DeleteLesson,
+    StudyLesson,
+    Search,
+    RandomPending,
+    OpenTree,
+    ChooseSort,
+    ShowStatistics,
+    ShowLeeches,
+    RecommendNext,
+    PlanPath,
+    IncreasePace,
+    DecreasePace,
+    RaisePriority,
+    LowerPriority,
+    ToggleMark,
+    VisualSelect,
+    ClearMarks,
+    OpenBulkActions,
+    MarkResults,
+    MergeLesson,
+    SplitLesson,
+    AddPart,
+    RemovePart,
+    RenamePart,
+    PreviousPart,
+    NextPart,
+    ToggleCollapse,
+    ToggleTreeDirection,
+    SelectNext,
+    SelectPrevious,
+    MoveLeft,
+    MoveRight,
+    IncreaseStep,
+    DecreaseStep,
+    ManualOverride,
+    ToggleSelection,
+    NextField,
+    PreviousField,
+    AddPrerequisite,
+    RemovePrerequisite,
+    BrowseResults,
+    EditSearch,
+    CursorLeft,
+    CursorRight,
+    PreviousWord,
+    NextWord,
+    LineStart,
+    LineEnd,
+    DeleteBackward,
+    DeleteForward,
+    DeleteWord,
+    DeleteToStart,
+    DeleteToEnd,
+    ShowHelp,
+    ScrollDown,
+    ScrollUp,
+    Confirm,
+    DeleteReconnecting,
+    Cancel,
+}
+
+impl Action {
+    /// A short description of the action, used to generate help messages.
+    pub fn description(&self) -> &'static str {
+        match self {
+            Action::Quit => "quit",
+            Action::AddLesson => "add a lesson",
+            Action::EditLesson => "edit the selected lesson",
+            Action::DeleteLesson => "delete the selected lesson",
+            Action::StudyLesson => "study the selected lesson",
+            Action::Search => "search for a lesson",
+            Action::RandomPending => "select a random pending lesson",
+            Action::OpenTree => "explore the dependency tree of the selected lesson",
+            Action::ChooseSort => "change the order of the lessons",
+            Action::ShowStatistics => "show statistics",
+            Action::PlanPath => "list the lessons to study before this one",
+            Action::IncreasePace => "study more lessons a day",
+            Action::DecreasePace => "study fewer lessons a day",
+            Action::RecommendNext => "go to the lesson that is the most useful to study now",
+            Action::RaisePriority => "raise the priority of the lesson",
+            Action::LowerPriority => "lower the priority of the lesson",
+            Action::ShowLeeches => "list the lessons that keep being forgotten",
+            Action::ToggleMark => "mark or unmark the selected lesson",
+            Action::VisualSelect => "start or end marking every lesson moved over",
+            Action::ClearMarks => "unmark every lesson",
+            Action::OpenBulkActions => "act on the marked lessons at once",
+            Action::MarkResults => "mark every result in the lesson list",
+            Action::MergeLesson => "merge another lesson into the selected one",
+            Action::SplitLesson => "split the selected lesson into several",
+            Action::AddPart => "add a part",
+            Action::RemovePart => "remove the selected part",
+            Action::RenamePart => "rename the selected part",
+            Action::PreviousPart => "give the selected lesson to the previous part",
+            Action::NextPart => "give the selected lesson to the next part",
+            Action::ToggleCollapse => "collapse or expand the selected lesson",
+            Action::ToggleTreeDirection => "switch between prerequisites and dependents",
+            Action::SelectNext => "select the next item",
+            Action::SelectPrevious => "select the previous item",
+            Action::MoveLeft => "select the option on the left",
+            Action::MoveRight => "select the option on the right",
+            Action::IncreaseStep => "increase the step",
+            Action::DecreaseStep => "decrease the step",
+            Action::ToggleSelection => "select or unselect the lesson",
+            Action::ManualOverride => "switch between grading and choosing the outcome by hand",
+            Action::NextField => "go to the next field",
+            Action::PreviousField => "go to the previous field",
+            Action::AddPrerequisite => "add a prerequisite",
+            Action::RemovePrerequisite => "remove the selected prerequisite",
+            Action::BrowseResults => "browse the results",
+            Action::EditSearch => "edit the search",
+            Action::CursorLeft => "move the cursor left",
+            Action::CursorRight => "move the cursor right",
+            Action::PreviousWord => "move the cursor to the previous word",
+            Action::NextWord => "move the cursor to the end of the word",
+            Action::LineStart => "move the cursor to the start of the text",
+            Action::LineEnd => "move the cursor to the end of the text",
+            Action::DeleteBackward => "delete the character before the cursor",
+            Action::DeleteForward => "delete the character under the cursor",
+            Action::DeleteWord => "delete the word before the cursor",
+            Action::DeleteToStart => "delete everything before the cursor",
+            Action::DeleteToEnd => "delete everything after the cursor",
+            Action::ShowHelp => "show or hide the keys available here",
+            Action::ScrollDown => "scroll down",
+            Action::ScrollUp => "scroll up",
+            Action::Confirm => "confirm",
+            Action::DeleteReconnecting => {
+                "delete the lesson, making its dependents require its prerequisites instead"
+            }
+            Action::Cancel => "cancel",
+        }
+    }
+}
+
+/// A key, along with the modifiers that need to be held for it to trigger.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "String")]
+pub struct KeyBinding {
+    code: KeyCode,
+    modifiers: KeyModifiers,
+}
+
+impl KeyBinding {
+    pub fn new(code: KeyCode, modifiers: KeyModifiers) -> Self {
+        Self { code, modifiers }.normalized()
+    }
+
+    pub fn key(code: KeyCode) -> Self {
+        Self::new(code, KeyModifiers::NONE)
+    }
+
+    pub fn char(c: char) -> Self {
+        Self::key(KeyCode::Char(c))
+    }
+
+    pub fn alt(c: char) -> Self {
+        Self::new(KeyCode::Char(c), KeyModifiers::ALT)
+    }
+
+    pub fn ctrl(c: char) -> Self {
+        Self::new(KeyCode::Char(c), KeyModifiers::CONTROL)
+    }
+
+    /// Terminals are inconsistent as to whether they report shift for uppercase characters and
+    /// back-tab, so it is ignored for those keys.
+    fn normalized(mut self) -> Self {
+        if matches!(self.code, KeyCode::Char(_) | KeyCode::BackTab) {
+            self.modifiers.remove(KeyModifiers::SHIFT);
+        }
+        self
+    }
+
+    pub fn matches(&self, key: &KeyEvent) -> bool {
+        *self == KeyBinding::new(key.code, key.modifiers)
+    }
+}
+
+#[derive(Debug)]
+pub struct KeyBindingParseError(String);
+
+impl std::fmt::Display for KeyBindingParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "invalid key binding \"{}\"", self.0)
+    }
+}
+
+impl std::str::FromStr for KeyBinding {
+    type Err = KeyBindingParseError;
+
+    /// Parses bindings such as `q`, `Q`, `enter`, `alt-j` or `ctrl-shift-tab`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = || KeyBindingParseError(s.to_string());
+
+        let mut modifiers = KeyModifiers::NONE;
+        let mut rest = s;
+        // the last character may itself be a '-', so we only strip prefixes when something
+        // remains after them
+        while let Some((prefix, key)) = rest.split_once('-').filter(|(_, key)| !key.is_empty()) {
+            modifiers |= match prefix.to_lowercase().as_str() {
+                "ctrl" => KeyModifiers::CONTROL,
+                "alt" => KeyModifiers::ALT,
+                "shift" => KeyModifiers::SHIFT,
+                _ => return Err(error()),
+            };
+            rest = key;
+        }
+
+        let mut chars = rest.chars();
+        let code = match (chars.next(), chars.next()) {
+            (Some(c), None) => KeyCode::Char(c),
+            _ => match rest.to_lowercase().as_str() {
+                "enter" => KeyCode::Enter,
+                "esc" => KeyCode::Esc,
+                "tab" if modifiers.contains(KeyModifiers::SHIFT) => KeyCode::BackTab,
+                "tab" => KeyCode::Tab,
+                "backtab" => KeyCode::BackTab,
+                "backspace" => KeyCode::Backspace,
+                "delete" => KeyCode::Delete,
+                "space" => KeyCode::Char(' '),
+                "up" => KeyCode::Up,
+                "down" => KeyCode::Down,
+                "left" => KeyCode::Left,
+                "right" => KeyCode::Right,
+                "home" => KeyCode::Home,
+                "end" => KeyCode::End,
+                "pageup" => KeyCode::PageUp,
+                "pagedown" => KeyCode::PageDown,
+                f if f.starts_with('f') => KeyCode::F(f[1..].parse().map_err(|_| error())?),
+                _ => return Err(error()),
+            },
+        };
+
+        Ok(KeyBinding::new(code, modifiers))
+    }
+}
+
+impl TryFrom<String> for KeyBinding {
+    type Error = KeyBindingParseError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl std::fmt::Display for KeyBinding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.modifiers.contains(KeyModifiers::CONTROL) {
+            write!(f, "Ctrl-")?;
+        }
+        if self.modifiers.contains(KeyModifiers::ALT) {
+            write!(f, "Alt-")?;
+        }
+        if self.modifiers.contains(KeyModifiers::SHIFT) {
+            write!(f, "Shift-")?;
+        }
+        match self.code {
+            KeyCode::Char(' ') => write!(f, "Space"),
+            KeyCode::Char(c) => write!(f, "{c}"),
+            KeyCode::BackTab => write!(f, "Shift-Tab"),
+            KeyCode::F(n) => write!(f, "F{n}"),
+            code => write!(f, "{code:?}"),
+        }
+    }
+}
+
+/// User overrides of the default bindings. For every context, the listed actions have their
+/// keys replaced by the given ones.
+pub type KeymapOverrides = HashMap<KeyContext, HashMap<Action, Vec<KeyBinding>>>;
+
+/// The central registry of every key binding of the application. Handlers ask it which action a
+/// key corresponds to, and help messages are generated from it.
+#[derive(Debug, Clone)]
+pub struct Keymap {
+    bindings: HashMap<KeyContext, Vec<(Action, Vec<KeyBinding>)>>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        use KeyCode::{BackTab, Backspace, Delete, End, Enter, Esc, Home, Left, Right, Tab, F};
+        use KeyContext::*;
+
+        let key = KeyBinding::key;
+        let char = KeyBinding::char;
+        let alt = KeyBinding::alt;
+        let ctrl = KeyBinding::ctrl;
+        let ctrl_key = |code| KeyBinding::new(code, KeyModifiers::CONTROL);
+
+        let bindings = HashMap::from([
+            (
+                Browsing,
+                vec![
+                    (Action::Quit, vec![char('q')]),
+                    (Action::AddLesson, vec![char('a')]),
+                    (Action::EditLesson, vec![char('e')]),
+                    (Action::DeleteLesson, vec![char('d')]),
+                    (Action::StudyLesson, vec![char('l')]),
+                    (Action::Search, vec![char('/')]),
+                    (Action::RandomPending, vec![char('r')]),
+                    (Action::OpenTree, vec![char('t')]),
+                    (Action::ChooseSort, vec![char('s')]),
+                    (Action::ShowStatistics, vec![char('S')]),
+                    (Action::ShowLeeches, vec![char('L')]),
+                    (Action::RecommendNext, vec![char('n')]),
+                    (Action::PlanPath, vec![char('p')]),
+                    (Action::RaisePriority, vec![char('+')]),
+                    (Action::LowerPriority, vec![char('-')]),
+                    (Action::ToggleMark, vec![char(' ')]),
+                    (Action::VisualSelect, vec![char('v')]),
+                    (Action::ClearMarks, vec![key(Esc)]),
+                    (Action::OpenBulkActions, vec![char('b')]),
+                    (Action::MergeLesson, vec![char('M')]),
+                    (Action::SplitLesson, vec![char('X')]),
+                ],
+            ),
+            (
+                List,
+                vec![
+                    (Action::SelectNext, vec![char('j')]),
+                    (Action::SelectPrevious, vec![char('k')]),
+                ],
+            ),
+            (
+                DeletionConfirmation,
+                vec![
+                    (Action::Confirm, vec![char('Y')]),
+                    (Action::DeleteReconnecting, vec![char('r')]),
+                    (Action::Cancel, vec![char('n'), key(Esc)]),
+                ],
+            ),
+            (
+                StudyEditor,
+                vec![
+                    (Action::MoveLeft, vec![char('h'), key(BackTab)]),
+                    (Action::MoveRight, vec![char('l'), key(Tab)]),
+                    (Action::IncreaseStep, vec![char('j')]),
+                    (Action::DecreaseStep, vec![char('k')]),
+                    (Action::ManualOverride, vec![char('m')]),
+                    (Action::Confirm, vec![key(Enter)]),
+                    (Action::Cancel, vec![key(Esc)]),
+                ],
+            ),
+            (
+                LessonName,
+                vec![
+                    (Action::NextField, vec![key(Tab), key(Enter), alt('j')]),
+                    (Action::Cancel, vec![key(Esc)]),
+                ],
+            ),
+            (
+                LessonTags,
+                vec![
+                    (Action::NextField, vec![key(Tab), key(Enter), alt('j')]),
+                    (Action::PreviousField, vec![key(BackTab), alt('k')]),
+                    (Action::Cancel, vec![key(Esc)]),
+                ],
+            ),
+            (
+                LessonDeadline,
+                vec![
+                    (Action::NextField, vec![key(Tab), key(Enter), alt('j')]),
+                    (Action::PreviousField, vec![key(BackTab), alt('k')]),
+                    (Action::Cancel, vec![key(Esc)]),
+                ],
+            ),
+            (
+                LessonPrerequisites,
+                vec![
+                    (Action::AddPrerequisite, vec![char('a')]),
+                    (Action::RemovePrerequisite, vec![char('d')]),
+                    (Action::NextField, vec![key(Tab), alt('j')]),
+                    (Action::PreviousField, vec![key(BackTab), alt('k')]),
+                    (Action::Cancel, vec![key(Esc)]),
+                ],
+            ),
+            (
+                LessonValidation,
+                vec![
+                    (Action::Confirm, vec![key(Enter)]),
+                    (Action::PreviousField, vec![key(BackTab), alt('k')]),
+                    (Action::Cancel, vec![key(Esc)]),
+                ],
+            ),
+            (
+                SearchInput,
+                vec![(Action::BrowseResults, vec![key(Enter), key(Esc)])],
+            ),
+            (
+                SearchResults,
+                vec![
+                    (Action::Confirm, vec![key(Enter)]),
+                    (Action::EditSearch, vec![char('a'), char('i')]),
+                    (Action::MarkResults, vec![char('m')]),
+                    (Action::Cancel, vec![key(Esc)]),
+                ],
+            ),
+            (
+                LessonTree,
+                vec![
+                    (Action::ToggleCollapse, vec![char(' ')]),
+                    (Action::ToggleTreeDirection, vec![char('t')]),
+                    (Action::Confirm, vec![key(Enter)]),
+                    (Action::Cancel, vec![key(Esc), char('q')]),
+                ],
+            ),
+            (
+                Statistics,
+                vec![(Action::Cancel, vec![key(Esc), char('q')])],
+            ),
+            (
+                LearningPath,
+                vec![
+                    (Action::StudyLesson, vec![char('l')]),
+                    (Action::IncreasePace, vec![char('+')]),
+                    (Action::DecreasePace, vec![char('-')]),
+                    (Action::Confirm, vec![key(Enter)]),
+                    (Action::Cancel, vec![key(Esc), char('q')]),
+                ],
+            ),
+            (
+                ReviewPrompt,
+                vec![
+                    (Action::ToggleSelection, vec![char(' ')]),
+                    (Action::Confirm, vec![key(Enter)]),
+                    (Action::Cancel, vec![key(Esc)]),
+                ],
+            ),
+            (
+                SortSelector,
+                vec![
+                    (Action::Confirm, vec![key(Enter)]),
+                    (Action::Cancel, vec![key(Esc)]),
+                ],
+            ),
+            (
+                BulkActions,
+                vec![
+                    (Action::Confirm, vec![key(Enter)]),
+                    (Action::Cancel, vec![key(Esc)]),
+                ],
+            ),
+            (
+                LessonSplitter,
+                vec![
+                    (Action::AddPart, vec![char('a')]),
+                    (Action::RenamePart, vec![char('r')]),
+                    (Action::RemovePart, vec![char('d')]),
+                    (Action::PreviousPart, vec![char('h')]),
+                    (Action::NextPart, vec![char('l')]),
+                    (Action::Confirm, vec![key(Enter)]),
+                    (Action::Cancel, vec![key(Esc)]),
+                ],
+            ),
+            (
+                SplitPartName,
+                vec![(Action::Confirm, vec![key(Enter), key(Esc)])],
+            ),
+            (
+                TagPrompt,
+                vec![
+                    (Action::Confirm, vec![key(Enter)]),
+                    (Action::Cancel, vec![key(Esc)]),
+                ],
+            ),
+            (
+                TextInput,
+                vec![
+                    (Action::CursorLeft, vec![key(Left), ctrl('b')]),
+                    (Action::CursorRight, vec![key(Right), ctrl('f')]),
+                    (Action::PreviousWord, vec![ctrl_key(Left), alt('b')]),
+                    (Action::NextWord, vec![ctrl_key(Right), alt('f')]),
+                    (Action::LineStart, vec![key(Home), ctrl('a')]),
+                    (Action::LineEnd, vec![key(End), ctrl('e')]),
+                    (Action::DeleteBackward, vec![key(Backspace)]),
+                    (Action::DeleteForward, vec![key(Delete), ctrl('d')]),
+                    (
+                        Action::DeleteWord,
+                        vec![ctrl('w'), KeyBinding::new(Backspace, KeyModifiers::ALT)],
+                    ),
+                    (Action::DeleteToStart, vec![ctrl('u')]),
+                    (Action::DeleteToEnd, vec![ctrl('k')]),
+                ],
+            ),
+            (
+                Help,
+                vec![
+                    (Action::ShowHelp, vec![char('?'), key(F(1))]),
+                    (Action::ScrollDown, vec![char('j'), key(KeyCode::Down)]),
+                    (Action::ScrollUp, vec![char('k'), key(KeyCode::Up)]),
+                    (Action::Cancel, vec![key(Esc), char('q')]),
+                ],
+            ),
+        ]);
+
+        Self { bindings }
+    }
+}
+
+impl Keymap {
+    /// Return the default keymap, with the bindings in `overrides` replacing the default ones.
+    /// A key given to an action can't already be bound to another action of the same context,
+    /// as only one of them could be used.
+    pub fn with_overrides(overrides: KeymapOverrides) -> Result<Self, ConfigError> {
+        let mut keymap = Self::default();
+        for (&context, actions) in &overrides {
+            let bindings = keymap.bindings.entry(context).or_default();
+            for (&action, keys) in actions {
+                match bindings.iter_mut().find(|(a, _)| *a == action) {
+                    Some((_, current_keys)) => current_keys.clone_from(keys),
+                    None => bindings.push((action, keys.clone())),
+                }
+            }
+        }
+
+        for (&context, actions) in &overrides {
+            for (&action, keys) in actions {
+                for key in keys {
+                    let conflict = keymap
+                        .bindings(context)
+                        .iter()
+                        .find(|(other, other_keys)| *other != action && other_keys.contains(key));
+                    if let Some(&(other, _)) = conflict {
+                        return Err(ConfigError::KeyConflict(context, *key, action, other));
+                    }
+                }
+            }
+        }
+        Ok(keymap)
+    }
+
+    /// Return the action bound to `key` in `context`, if any.
+    pub fn action(&self, context: KeyContext, key: &KeyEvent) -> Option<Action> {
+        self.bindings(context)
+            .iter()
+            .find(|(_, keys)| keys.iter().any(|binding| binding.matches(key)))
+            .map(|(action, _)| *action)
+    }
+
+    /// Return every action available in `context`, along with the keys bound to them.
+    pub fn bindings(&self, context: KeyContext) -> &[(Action, Vec<KeyBinding>)] {
+        self.bindings
+            .get(&context)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    /// Return the keys bound to `action` in `context`.
+    pub fn keys(&self, context: KeyContext, action: Action) -> &[KeyBinding] {
+        self.bindings(context)
+            .iter()
+            .find(|(a, _)| *a == action)
+            .map(|(_, keys)| keys.as_slice())
+            .unwrap_or_default()
+    }
+
+    /// A human readable list of the keys bound to `action` in `context`, such as `'a'/'i'`.
+    pub fn describe_keys(&self, context: KeyContext, action: Action) -> String {
+        self.keys(context, action)
+            .iter()
+            .map(|key| format!("'{key}'"))
+            .collect::<Vec<_>>()
+            .join("/")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_key_binding_parsing() {
+        assert_eq!("q".parse::<KeyBinding>().unwrap(), KeyBinding::char('q'));
+        assert_eq!("-".parse::<KeyBinding>().unwrap(), KeyBinding::char('-'));
+        assert_eq!("alt-j".parse::<KeyBinding>().unwrap(), KeyBinding::alt('j'));
+        assert_eq!(
+            "ctrl-w".parse::<KeyBinding>().unwrap(),
+            KeyBinding::new(KeyCode::Char('w'), KeyModifiers::CONTROL)
+        );
+        assert_eq!(
+            "shift-tab".parse::<KeyBinding>().unwrap(),
+            KeyBinding::key(KeyCode::BackTab)
+        );
+        assert_eq!(
+            "Enter".parse::<KeyBinding>().unwrap(),
+            KeyBinding::key(KeyCode::Enter)
+        );
+        assert!("hyper-x".parse::<KeyBinding>().is_err());
+        assert!("notakey".parse::<KeyBinding>().is_err());
+    }
+
+    #[test]
+    fn test_overrides() {
+        let overrides = HashMap::from([(
+            KeyContext::Browsing,
+            HashMap::from([(Action::StudyLesson, vec![KeyBinding::char('w')])]),
+        )]);
+        let keymap = Keymap::with_overrides(overrides).unwrap();
+
+        let w = KeyEvent::new(KeyCode::Char('w'), KeyModifiers::NONE);
+        let l = KeyEvent::new(KeyCode::Char('l'), KeyModifiers::NONE);
+        let shift_y = KeyEvent::new(KeyCode::Char('Y'), KeyModifiers::SHIFT);
+
+        assert_eq!(
+            keymap.action(KeyContext::Browsing, &w),
+            Some(Action::StudyLesson)
+        );
+        assert_eq!(keymap.action(KeyContext::Browsing, &l), None);
+        assert_eq!(
+            keymap.action(KeyContext::StudyEditor, &l),
+            Some(Action::MoveRight)
+        );
+        assert_eq!(
+            keymap.action(KeyContext::DeletionConfirmation, &shift_y),
+            Some(Action::Confirm)
+        );
+    }
+
+    #[test]
+    fn test_override_conflict() {
+        // 'a' already adds a lesson
+        let overrides = HashMap::from([(
+            KeyContext::Browsing,
+            HashMap::from([(Action::StudyLesson, vec![KeyBinding::char('a')])]),
+        )]);
+        assert!(matches!(
+            Keymap::with_overrides(overrides),
+            Err(ConfigError::KeyConflict(
+                KeyContext::Browsing,
+                _,
+                Action::StudyLesson,
+                Action::AddLesson
+            ))
+        ));
+
+        // unless it is rebound too
+        let overrides = HashMap::from([(
+            KeyContext::Browsing,
+            HashMap::from([
+                (Action::StudyLesson, vec![KeyBinding::char('a')]),
+                (Action::AddLesson, vec![KeyBinding::char('A')]),
+            ]),
+        )]);
+        assert!(Keymap::with_overrides(overrides).is_ok());
+    }
+
+    #[test]
+    fn test_help_key_is_free() {
+        let keymap = Keymap::default();
+        let question_mark = KeyEvent::new(KeyCode::Char('?'), KeyModifiers::SHIFT);
+
+        assert_eq!(
+            keymap.action(KeyContext::Help, &question_mark),
+            Some(Action::ShowHelp)
+        );
+        for &context in keymap.bindings.keys() {
+            if context != KeyContext::Help {
+                assert_eq!(keymap.action(context, &question_mark), None);
+            }
+        }
+    }
+}
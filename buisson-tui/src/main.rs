@@ -1,20 +1,36 @@
 use crossterm::{
-    event,
+    event::{
+        self, DisableBracketedPaste, DisableMouseCapture, EnableBracketedPaste, EnableMouseCapture,
+    },
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
     ExecutableCommand,
 };
 
-use buisson_tui::app::{App, AppError};
+use buisson_tui::{
+    app::{App, AppError},
+    cli,
+};
 use cli_log::*;
 use ratatui::prelude::{CrosstermBackend, Terminal};
 use std::io::stdout;
 
 fn main() -> Result<(), AppError> {
     init_cli_log!();
+
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    if !args.is_empty() {
+        return cli::run(&args);
+    }
+
     stdout()
         .execute(EnterAlternateScreen)
         .map_err(AppError::IOError)?;
     enable_raw_mode().map_err(AppError::IOError)?;
+    stdout()
+        .execute(EnableMouseCapture)
+        .map_err(AppError::IOError)?
+        .execute(EnableBracketedPaste)
+        .map_err(AppError::IOError)?;
     let mut terminal = Terminal::new(CrosstermBackend::new(stdout())).map_err(AppError::IOError)?;
     terminal.clear().map_err(AppError::IOError)?;
 
@@ -32,6 +48,11 @@ fn main() -> Result<(), AppError> {
         }
     }
 
+    stdout()
+        .execute(DisableMouseCapture)
+        .map_err(AppError::IOError)?
+        .execute(DisableBracketedPaste)
+        .map_err(AppError::IOError)?;
     stdout()
         .execute(LeaveAlternateScreen)
         .map_err(AppError::IOError)?;
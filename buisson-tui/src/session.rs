@@ -0,0 +1,37 @@
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use buisson_common::sort::SortOrder;
+
+/// The state of the interface that is kept between sessions, in `session.ron` in the XDG data
+/// directory. Unlike the configuration, it is written by the program and not by the user.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Session {
+    /// the order of the main lesson list
+    pub sort_order: SortOrder,
+    #[serde(skip)]
+    path: PathBuf,
+}
+
+impl Session {
+    /// Read the session saved at `path`. If it doesn't exist or can't be read, for instance
+    /// because it was written by another version of the program, a new one is started.
+    pub fn load(path: &Path) -> Self {
+        let session = std::fs::read_to_string(path)
+            .ok()
+            .and_then(|text| ron::from_str(&text).ok())
+            .unwrap_or_default();
+
+        Self {
+            path: path.to_path_buf(),
+            ..session
+        }
+    }
+
+    pub fn save(&self) -> std::io::Result<()> {
+        let text = ron::to_string(self).expect("the session to be serializable");
+        std::fs::write(&self.path, text)
+    }
+}
@@ -0,0 +1,78 @@
+use std::collections::HashMap;
+
+use buisson_common::{IOBackend, Id, LessonInfo, Review};
+use buisson_database::{DirectoryBackend, DirectoryError, SQLiteBackend};
+
+/// Where the lessons are stored: in the database by default, or in a directory if one is set in
+/// the configuration.
+#[derive(Debug)]
+pub enum Storage {
+    Database(SQLiteBackend),
+    Directory(DirectoryBackend),
+}
+
+#[derive(Debug)]
+pub enum StorageError {
+    Database(rusqlite::Error),
+    Directory(DirectoryError),
+}
+
+impl IOBackend for Storage {
+    type Error = StorageError;
+
+    fn query_lessons(&self) -> Result<HashMap<Id, LessonInfo>, Self::Error> {
+        match self {
+            Storage::Database(backend) => backend.query_lessons().map_err(StorageError::Database),
+            Storage::Directory(backend) => backend.query_lessons().map_err(StorageError::Directory),
+        }
+    }
+
+    fn add_new_lesson(&self, id: Id, lesson: &LessonInfo) -> Result<(), Self::Error> {
+        match self {
+            Storage::Database(backend) => backend
+                .add_new_lesson(id, lesson)
+                .map_err(StorageError::Database),
+            Storage::Directory(backend) => backend
+                .add_new_lesson(id, lesson)
+                .map_err(StorageError::Directory),
+        }
+    }
+
+    fn update_existing_lesson(&self, id: Id, lesson: &LessonInfo) -> Result<(), Self::Error> {
+        match self {
+            Storage::Database(backend) => backend
+                .update_existing_lesson(id, lesson)
+                .map_err(StorageError::Database),
+            Storage::Directory(backend) => backend
+                .update_existing_lesson(id, lesson)
+                .map_err(StorageError::Directory),
+        }
+    }
+
+    fn remove_lesson(&self, id: Id) -> Result<(), Self::Error> {
+        match self {
+            Storage::Database(backend) => backend.remove_lesson(id).map_err(StorageError::Database),
+            Storage::Directory(backend) => {
+                backend.remove_lesson(id).map_err(StorageError::Directory)
+            }
+        }
+    }
+
+    fn query_reviews(&self) -> Result<Vec<Review>, Self::Error> {
+        match self {
+            Storage::Database(backend) => backend.query_reviews().map_err(StorageError::Database),
+            Storage::Directory(backend) => backend.query_reviews().map_err(StorageError::Directory),
+        }
+    }
+
+    fn add_review(&self, review: &Review) -> Result<(), Self::Error> {
+        match self {
+            Storage::Database(backend) => {
+                backend.add_review(review).map_err(StorageError::Database)
+            }
+            Storage::Directory(backend) => {
+                backend.add_review(review).map_err(StorageError::Directory)
+            }
+        }
+    }
+}
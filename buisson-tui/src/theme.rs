@@ -0,0 +1,105 @@
+use ratatui::{
+    style::{Color, Style, Stylize},
+    text::{Line, Span},
+};
+use serde::Deserialize;
+
+use buisson_common::NodeStatus;
+
+/// The palettes that can be chosen in the configuration.
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+pub enum ThemeName {
+    /// Green, yellow and red, as in a traffic light.
+    #[default]
+    Default,
+    /// Blue, yellow and vermillion, from the Okabe-Ito palette, which stay distinguishable with
+    /// the most common forms of color blindness.
+    ColorblindSafe,
+    /// No colors at all, the status is conveyed with text modifiers.
+    Monochrome,
+}
+
+/// How the interface should convey information with colors and symbols.
+#[derive(Debug, Clone)]
+pub struct Theme {
+    ok: Style,
+    pending: Style,
+    missing_prereq: Style,
+    search_match: Style,
+    /// whether or not to display a symbol next to lesson names, so that their status can be read
+    /// without relying on colors.
+    status_glyphs: bool,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::new(ThemeName::Default, false)
+    }
+}
+
+impl Theme {
+    pub fn new(name: ThemeName, status_glyphs: bool) -> Self {
+        let (ok, pending, missing_prereq, search_match) = match name {
+            ThemeName::Default => (
+                Style::default().light_green(),
+                Style::default().light_yellow(),
+                Style::default().light_red(),
+                Style::default().blue(),
+            ),
+            ThemeName::ColorblindSafe => (
+                Style::default().fg(Color::Rgb(86, 180, 233)),
+                Style::default().fg(Color::Rgb(240, 228, 66)),
+                Style::default().fg(Color::Rgb(213, 94, 0)),
+                Style::default().bold().underlined(),
+            ),
+            ThemeName::Monochrome => (
+                Style::default(),
+                Style::default().bold(),
+                Style::default().dim(),
+                Style::default().underlined(),
+            ),
+        };
+
+        Self {
+            ok,
+            pending,
+            missing_prereq,
+            search_match,
+            status_glyphs,
+        }
+    }
+
+    pub fn style_from_status(&self, status: &NodeStatus) -> Style {
+        match status {
+            NodeStatus::Ok => self.ok,
+            NodeStatus::Pending => self.pending,
+            NodeStatus::MissingPrereq(_) => self.missing_prereq,
+        }
+    }
+
+    /// The style of the characters matching a search.
+    pub fn search_match_style(&self) -> Style {
+        self.search_match
+    }
+
+    /// The symbol to put before the name of a lesson with status `status`, if glyphs are enabled.
+    pub fn status_glyph(&self, status: &NodeStatus) -> Option<Span<'static>> {
+        if !self.status_glyphs {
+            return None;
+        }
+        let glyph = match status {
+            NodeStatus::Ok => "✓ ",
+            NodeStatus::Pending => "• ",
+            NodeStatus::MissingPrereq(_) => "✗ ",
+        };
+        Some(Span::styled(glyph, self.style_from_status(status)))
+    }
+
+    /// The name of a lesson, styled according to its status.
+    pub fn lesson_name<'a>(&self, name: &'a str, status: &NodeStatus) -> Line<'a> {
+        let mut spans = vec![];
+        spans.extend(self.status_glyph(status));
+        spans.push(Span::styled(name, self.style_from_status(status)));
+        Line::from(spans)
+    }
+}